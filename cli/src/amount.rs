@@ -0,0 +1,81 @@
+use serde::Serialize;
+
+// 10^38 is the largest power of ten an i128 holds
+const MAX_DECIMALS: u32 = 38;
+
+/// How a token's amounts read, from its `decimals()` and `symbol()`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Unit {
+    pub decimals: u32,
+    pub symbol: String,
+}
+
+impl Unit {
+    pub fn new(decimals: u32, symbol: &str) -> Result<Self, String> {
+        if decimals > MAX_DECIMALS {
+            return Err(format!("token reports {decimals} decimals"));
+        }
+        // The native asset contract calls itself `native`
+        let symbol = if symbol == "native" { "XLM" } else { symbol };
+        Ok(Unit { decimals, symbol: symbol.to_string() })
+    }
+
+    fn scale(&self) -> i128 {
+        10_i128.pow(self.decimals)
+    }
+}
+
+/// Parses a user-supplied amount into the token's base units. Plain numbers
+/// are whole tokens, optionally followed by the symbol (`2.5`, `2.5xlm`,
+/// `2.5 XLM`); a `stroops` suffix takes the value as base units as is
+/// (`25000000stroops`).
+pub fn parse_amount(input: &str, unit: &Unit) -> Result<i128, String> {
+    let lower = input.trim().to_ascii_lowercase();
+    let invalid = || format!("invalid amount `{input}`");
+
+    if let Some(stroops) = lower.strip_suffix("stroops") {
+        let stroops: i128 = stroops.trim().parse().map_err(|_| invalid())?;
+        return if stroops > 0 { Ok(stroops) } else { Err(invalid()) };
+    }
+
+    let symbol = unit.symbol.to_ascii_lowercase();
+    let tokens = lower.strip_suffix(symbol.as_str()).filter(|_| !symbol.is_empty()).unwrap_or(&lower).trim();
+    let (whole, frac) = tokens.split_once('.').unwrap_or((tokens, ""));
+    if whole.is_empty() && frac.is_empty() {
+        return Err(invalid());
+    }
+    if !whole.chars().all(|c| c.is_ascii_digit()) || !frac.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let decimals = unit.decimals as usize;
+    if frac.len() > decimals {
+        return Err(format!("`{input}` has more than {decimals} decimals"));
+    }
+
+    let whole: i128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+    let frac: i128 = if decimals == 0 { 0 } else { format!("{frac:0<decimals$}").parse().map_err(|_| invalid())? };
+    let amount = whole
+        .checked_mul(unit.scale())
+        .and_then(|w| w.checked_add(frac))
+        .ok_or_else(invalid)?;
+    if amount <= 0 {
+        return Err(format!("amount must be positive, got `{input}`"));
+    }
+    Ok(amount)
+}
+
+/// Base units as whole tokens, e.g. `15000000` → `1.50 XLM` for a token
+/// with 7 decimals. Trailing zeros are trimmed but at least two decimals
+/// kept.
+pub fn format_amount(amount: i128, unit: &Unit) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    let amount = amount.unsigned_abs();
+    let scale = unit.scale() as u128;
+    let decimals = unit.decimals as usize;
+    if decimals == 0 {
+        return format!("{sign}{amount} {}", unit.symbol);
+    }
+    let frac = format!("{:0decimals$}", amount % scale);
+    let kept = frac.trim_end_matches('0').len().max(decimals.min(2));
+    format!("{sign}{}.{} {}", amount / scale, &frac[..kept], unit.symbol)
+}
@@ -0,0 +1,211 @@
+use clap::Subcommand;
+use safe_hands_contract::Escrow;
+use serde::Serialize;
+use serde_json::{json, Value};
+use soroban_sdk::xdr::{ScAddress, ScVal};
+use soroban_sdk::{Address, Env, TryFromVal};
+
+use crate::amount::{format_amount, Unit};
+
+/// Addresses are strkeys (`G...`/`C...`). With `--offline` any other name
+/// stands for a dry-run account; live, names are `stellar` identities, and
+/// signers can also be given by secret key (`S...`).
+#[derive(Subcommand, Clone, Debug)]
+pub enum Command {
+    /// Lock funds for a new job; signed by the client
+    Deposit {
+        #[arg(long)]
+        client: String,
+        #[arg(long)]
+        freelancer: String,
+        #[arg(long)]
+        arbiter: String,
+        /// Whole tokens (`25`, `2.5xlm`) or base units (`25000000stroops`)
+        #[arg(long)]
+        amount: String,
+        #[arg(long, default_value_t = 30)]
+        deadline_days: u64,
+        /// Token contract; defaults to native XLM, or the dry-run token offline
+        #[arg(long)]
+        token: Option<String>,
+    },
+    /// Take the job as the freelancer; delivery can be approved from then on
+    Accept {
+        id: u64,
+        #[arg(long = "as")]
+        signer: String,
+        /// SHA-256 of the terms anchored at deposit, in hex, as `get` shows it
+        #[arg(long, value_parser = parse_hash)]
+        terms_hash: [u8; 32],
+    },
+    /// Approve the current milestone as the client or freelancer
+    Approve {
+        id: u64,
+        #[arg(long = "as")]
+        signer: String,
+    },
+    /// Refund the client before the freelancer accepts
+    Cancel {
+        id: u64,
+        #[arg(long = "as")]
+        signer: String,
+    },
+    Dispute {
+        id: u64,
+        #[arg(long = "as")]
+        signer: String,
+    },
+    /// Award the locked funds to the winner as the arbiter
+    Resolve {
+        id: u64,
+        #[arg(long = "as")]
+        signer: String,
+        #[arg(long)]
+        winner: String,
+    },
+    /// Settle an expired escrow; the signer collects any keeper bounty
+    ClaimTimeout {
+        id: u64,
+        #[arg(long = "as")]
+        signer: String,
+    },
+    Get {
+        id: u64,
+    },
+    /// Every escrow the user is a party or arbiter of
+    List {
+        user: String,
+    },
+    /// Mint test funds to an account; dry runs only
+    Fund {
+        account: String,
+        /// Whole tokens (`25`, `2.5xlm`) or base units (`25000000stroops`)
+        #[arg(long)]
+        amount: String,
+        /// Token contract; defaults to the dry-run token
+        #[arg(long)]
+        token: Option<String>,
+    },
+}
+
+/// A 32-byte hash in hex, with or without `0x`.
+pub fn parse_hash(text: &str) -> Result<[u8; 32], String> {
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    if text.len() != 64 || !text.is_ascii() {
+        return Err("expected 32 bytes as 64 hex digits".to_string());
+    }
+    let mut hash = [0; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[2 * i..2 * i + 2], 16).map_err(|_| format!("not hex: {text}"))?;
+    }
+    Ok(hash)
+}
+
+/// The hex form `accept --terms-hash` takes and `get` shows.
+pub fn hash_hex(hash: &[u8; 32]) -> String {
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Deposited(u64),
+    Done,
+    Escrow(Box<EscrowView>),
+    Escrows(Vec<EscrowView>),
+}
+
+/// What `get` and `list` show of an escrow. Amounts are in the token's
+/// base units.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct EscrowView {
+    pub id: u64,
+    pub state: String,
+    pub client: String,
+    pub freelancer: String,
+    pub arbiter: String,
+    pub token: String,
+    #[serde(serialize_with = "as_string")]
+    pub amount: i128,
+    #[serde(serialize_with = "as_string")]
+    pub released_amount: i128,
+    #[serde(serialize_with = "as_string")]
+    pub locked_amount: i128,
+    pub deadline: u64,
+    pub terms_hash: String,
+    #[serde(flatten)]
+    pub unit: Unit,
+}
+
+// i128 doesn't fit in a JSON number for most readers
+fn as_string<S: serde::Serializer>(amount: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&amount.to_string())
+}
+
+impl EscrowView {
+    pub fn new(id: u64, escrow: &Escrow, unit: Unit) -> Self {
+        EscrowView {
+            id,
+            state: format!("{:?}", escrow.state),
+            client: strkey(&escrow.client),
+            freelancer: strkey(&escrow.freelancer),
+            arbiter: strkey(&escrow.arbiter),
+            token: strkey(&escrow.token),
+            amount: escrow.amount,
+            released_amount: escrow.released_amount,
+            locked_amount: escrow.locked_amount,
+            deadline: escrow.deadline,
+            terms_hash: hash_hex(&escrow.terms_hash.to_array()),
+            unit,
+        }
+    }
+}
+
+/// The strkey (`G...`/`C...`) form of an address.
+pub fn strkey(address: &Address) -> String {
+    ScAddress::from(address).to_string()
+}
+
+pub(crate) fn sc_address(env: &Env, address: ScAddress) -> Address {
+    Address::try_from_val(env, &ScVal::Address(address)).unwrap()
+}
+
+impl Outcome {
+    pub fn to_json(&self) -> Value {
+        match self {
+            Outcome::Deposited(id) => json!({ "escrow_id": id }),
+            Outcome::Done => json!({ "ok": true }),
+            Outcome::Escrow(view) => json!(view),
+            Outcome::Escrows(views) => json!(views),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        match self {
+            Outcome::Deposited(id) => format!("Created escrow #{id}"),
+            Outcome::Done => "Done".to_string(),
+            Outcome::Escrow(view) => view_text(view),
+            Outcome::Escrows(views) if views.is_empty() => "No escrows".to_string(),
+            Outcome::Escrows(views) => views
+                .iter()
+                .map(|v| format!("#{:<5} {:<10} {:>20}  deadline {}", v.id, v.state, format_amount(v.amount, &v.unit), v.deadline))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+fn view_text(view: &EscrowView) -> String {
+    [
+        format!("Escrow #{} ({})", view.id, view.state),
+        format!("  client      {}", view.client),
+        format!("  freelancer  {}", view.freelancer),
+        format!("  arbiter     {}", view.arbiter),
+        format!("  token       {}", view.token),
+        format!("  amount      {}", format_amount(view.amount, &view.unit)),
+        format!("  released    {}", format_amount(view.released_amount, &view.unit)),
+        format!("  locked      {}", format_amount(view.locked_amount, &view.unit)),
+        format!("  deadline    {}", view.deadline),
+        format!("  terms hash  {}", view.terms_hash),
+    ]
+    .join("\n")
+}
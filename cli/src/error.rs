@@ -0,0 +1,58 @@
+use std::fmt;
+
+use safe_hands_contract::Error as ContractError;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Xdr(soroban_sdk::xdr::Error),
+    Http(String),
+    Rpc(i64, String), // JSON-RPC error code and message
+    Contract(ContractError), // The contract rejected the call
+    Host(String), // The call failed outside the contract, e.g. a failed token transfer
+    Config(String),
+    InvalidAddress(String),
+    InvalidAmount(String),
+    InvalidKey(String), // A signer that has no usable secret key
+    UnexpectedResponse(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{e}"),
+            Error::Json(e) => write!(f, "json: {e}"),
+            Error::Xdr(e) => write!(f, "xdr: {e}"),
+            Error::Http(e) => write!(f, "http: {e}"),
+            Error::Rpc(code, message) => write!(f, "rpc error {code}: {message}"),
+            Error::Contract(e) => write!(f, "contract error #{}: {e:?}", *e as u32),
+            Error::Host(e) => write!(f, "call failed: {e}"),
+            Error::Config(e) => write!(f, "{e}"),
+            Error::InvalidAddress(a) => write!(f, "invalid address `{a}`"),
+            Error::InvalidAmount(e) => write!(f, "{e}"),
+            Error::InvalidKey(e) => write!(f, "{e}"),
+            Error::UnexpectedResponse(e) => write!(f, "unexpected response from the RPC server: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<soroban_sdk::xdr::Error> for Error {
+    fn from(e: soroban_sdk::xdr::Error) -> Self {
+        Error::Xdr(e)
+    }
+}
@@ -0,0 +1,118 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use ed25519_dalek::{Signer as _, SigningKey};
+use soroban_sdk::xdr::{AccountId, DecoratedSignature, PublicKey, ScAddress, Signature, SignatureHint, Uint256};
+use stellar_strkey::ed25519;
+
+use crate::error::Error;
+
+/// Signs transactions for an account. `--as` and a deposit's `--client`
+/// take a secret key (`S...`) or the name of a `stellar` identity that was
+/// stored with one.
+pub struct Signer {
+    key: SigningKey,
+}
+
+impl Signer {
+    pub fn resolve(name: &str) -> Result<Self, Error> {
+        let secret = match name.starts_with('S') && name.len() == 56 {
+            true => name.to_string(),
+            false => match identity(name)? {
+                Identity::Secret(secret) => secret,
+                Identity::Public(_) => {
+                    return Err(Error::InvalidKey(format!("identity `{name}` has no secret key to sign with")))
+                }
+            },
+        };
+        let seed = ed25519::PrivateKey::from_string(&secret)
+            .map_err(|_| Error::InvalidKey(format!("`{name}` does not hold a valid secret key")))?;
+        Ok(Signer { key: SigningKey::from_bytes(&seed.0) })
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.key.verifying_key().to_bytes()
+    }
+
+    pub fn address(&self) -> ScAddress {
+        account_address(self.public_key())
+    }
+
+    /// Signs a transaction's signature payload hash.
+    pub fn sign(&self, hash: &[u8; 32]) -> Result<DecoratedSignature, Error> {
+        let public_key = self.public_key();
+        Ok(DecoratedSignature {
+            hint: SignatureHint(public_key[28..].try_into().unwrap()),
+            signature: Signature(self.key.sign(hash).to_bytes().try_into()?),
+        })
+    }
+}
+
+/// A strkey (`G...`/`C...`), or the account of a `stellar` identity.
+pub fn resolve_address(name: &str) -> Result<ScAddress, Error> {
+    if let Ok(address) = ScAddress::from_str(name) {
+        return Ok(address);
+    }
+    if name.len() == 56 && (name.starts_with('G') || name.starts_with('C')) {
+        return Err(Error::InvalidAddress(name.to_string()));
+    }
+    match identity(name)? {
+        Identity::Public(public_key) => Ok(account_address(public_key)),
+        Identity::Secret(_) => Ok(Signer::resolve(name)?.address()),
+    }
+}
+
+pub fn account_address(public_key: [u8; 32]) -> ScAddress {
+    ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(public_key))))
+}
+
+enum Identity {
+    Secret(String),
+    Public([u8; 32]),
+}
+
+// Reads `<name>.toml` from the `stellar` CLI's identity directories, the
+// project-local one first
+fn identity(name: &str) -> Result<Identity, Error> {
+    let not_found = || Error::InvalidKey(format!("no address, secret key or `stellar` identity named `{name}`"));
+    if name.is_empty() || name.contains(['/', '\\', '.']) {
+        return Err(not_found());
+    }
+    let Some(text) = identity_dirs().iter().find_map(|dir| fs::read_to_string(dir.join(format!("{name}.toml"))).ok()) else {
+        return Err(not_found());
+    };
+
+    let field = |key: &str| {
+        text.lines().find_map(|line| {
+            let (k, v) = line.split_once('=')?;
+            (k.trim() == key).then(|| v.trim().trim_matches('"').to_string())
+        })
+    };
+    if let Some(secret) = field("secret_key") {
+        return Ok(Identity::Secret(secret));
+    }
+    if let Some(public_key) = field("public_key") {
+        let public_key = ed25519::PublicKey::from_string(&public_key)
+            .map_err(|_| Error::InvalidKey(format!("identity `{name}` has an invalid public key")))?;
+        return Ok(Identity::Public(public_key.0));
+    }
+    Err(Error::InvalidKey(format!(
+        "identity `{name}` is not stored as a secret key; pass it with `--as $(stellar keys show {name})`"
+    )))
+}
+
+fn identity_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(".stellar/identity")];
+    if let Ok(home) = env::var("STELLAR_CONFIG_HOME") {
+        dirs.push(PathBuf::from(home).join("identity"));
+    }
+    let config = env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| PathBuf::from(home).join(".config")));
+    if let Ok(config) = config {
+        dirs.push(config.join("stellar/identity"));
+    }
+    dirs
+}
@@ -0,0 +1,13 @@
+//! `safehands`: operate SafeHands escrows from the command line, live
+//! through a Soroban RPC server or as dry runs against an in-process ledger.
+
+pub mod amount;
+pub mod command;
+pub mod error;
+pub mod keys;
+pub mod live;
+pub mod offline;
+pub mod rpc;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use safe_hands_contract::{Error as ContractError, Escrow, EscrowPage, EscrowState, Role};
+use soroban_sdk::testutils::EnvTestConfig;
+use soroban_sdk::xdr::{
+    HostFunction, InvokeContractArgs, InvokeHostFunctionOp, Limits, Memo, MuxedAccount, Operation, OperationBody,
+    Preconditions, ReadXdr, ScAddress, ScSymbol, ScVal, SequenceNumber, SorobanAuthorizationEntry, SorobanCredentials,
+    SorobanTransactionData, Transaction, TransactionEnvelope, TransactionExt, TransactionV1Envelope, Uint256,
+};
+use soroban_sdk::{Address, BytesN, Env, Error as HostError, IntoVal, TryFromVal, Val, Vec as SorobanVec};
+
+use crate::amount::{parse_amount, Unit};
+use crate::command::{sc_address, strkey, Command, EscrowView, Outcome};
+use crate::error::Error;
+use crate::keys::{resolve_address, Signer};
+use crate::rpc::{Network, Rpc, SimulatedCall, Simulation};
+
+const PAGE_LIMIT: u32 = 50;
+
+// Inclusion fee on top of the resource fee simulation asks for
+const BASE_FEE: u32 = 100;
+
+// Read-only calls are simulated from this account, which needn't exist
+const SIMULATION_SOURCE: [u8; 32] = [0; 32];
+
+/// Runs commands on a network through a Soroban RPC server. Each write is
+/// simulated, signed by the acting party and sent; reads are only
+/// simulated.
+pub struct LiveClient {
+    env: Env, // Converts between contract types and XDR
+    rpc: Rpc,
+    network: Network,
+    contract: Address,
+}
+
+impl LiveClient {
+    pub fn new(network: Network, contract_id: &str) -> Result<Self, Error> {
+        let env = Env::new_with_config(EnvTestConfig { capture_snapshot_at_drop: false });
+        let contract = match contract_id.parse() {
+            Ok(address @ ScAddress::Contract(_)) => sc_address(&env, address),
+            _ => return Err(Error::InvalidAddress(contract_id.to_string())),
+        };
+        Ok(LiveClient { env, rpc: Rpc::new(&network.rpc_url), network, contract })
+    }
+
+    pub fn run(&self, command: &Command) -> Result<Outcome, Error> {
+        let outcome = match command {
+            Command::Deposit { client, freelancer, arbiter, amount, deadline_days, token } => {
+                let signer = Signer::resolve(client)?;
+                let token = match token {
+                    Some(token) => self.address(token)?,
+                    None => sc_address(&self.env, self.network.native_token()?),
+                };
+                let amount = parse_amount(amount, &self.unit(&token)?).map_err(Error::InvalidAmount)?;
+                let args = (
+                    sc_address(&self.env, signer.address()),
+                    self.address(freelancer)?,
+                    self.address(arbiter)?,
+                    token,
+                    amount,
+                    *deadline_days,
+                );
+                Outcome::Deposited(self.write(&signer, "deposit", args)?)
+            }
+            Command::Accept { id, signer, terms_hash } => {
+                let terms_hash = BytesN::from_array(&self.env, terms_hash);
+                self.write_as(signer, "accept", |me| (me, *id, terms_hash))?;
+                Outcome::Done
+            }
+            Command::Approve { id, signer } => {
+                self.write_as(signer, "approve", |me| (me, *id))?;
+                Outcome::Done
+            }
+            Command::Cancel { id, signer } => {
+                self.write_as(signer, "cancel", |me| (me, *id))?;
+                Outcome::Done
+            }
+            Command::Dispute { id, signer } => {
+                self.write_as(signer, "dispute", |me| (me, *id))?;
+                Outcome::Done
+            }
+            Command::Resolve { id, signer, winner } => {
+                let winner = self.address(winner)?;
+                self.write_as(signer, "resolve", |me| (me, *id, winner))?;
+                Outcome::Done
+            }
+            Command::ClaimTimeout { id, signer } => {
+                self.write_as(signer, "claim_timeout", |me| (me, *id))?;
+                Outcome::Done
+            }
+            Command::Get { id } => {
+                let escrow: Escrow = self.read(&self.contract, "get_escrow", (*id,))?;
+                Outcome::Escrow(Box::new(EscrowView::new(*id, &escrow, self.unit(&escrow.token)?)))
+            }
+            Command::List { user } => {
+                let user = self.address(user)?;
+                let mut units: HashMap<String, Unit> = HashMap::new();
+                let mut views = Vec::new();
+                let mut cursor = Some(0);
+                while let Some(at) = cursor {
+                    let args = (user.clone(), None::<EscrowState>, None::<Role>, at, PAGE_LIMIT);
+                    let page: EscrowPage = self.read(&self.contract, "get_user_escrows_page", args)?;
+                    for entry in page.escrows.iter() {
+                        let token = strkey(&entry.escrow.token);
+                        let unit = match units.get(&token) {
+                            Some(unit) => unit.clone(),
+                            None => self.unit(&entry.escrow.token)?,
+                        };
+                        units.insert(token, unit.clone());
+                        views.push(EscrowView::new(entry.id, &entry.escrow, unit));
+                    }
+                    cursor = page.next_cursor;
+                }
+                Outcome::Escrows(views)
+            }
+            Command::Fund { .. } => {
+                return Err(Error::Config("`fund` mints dry-run tokens; use it with --offline".into()))
+            }
+        };
+        Ok(outcome)
+    }
+
+    fn address(&self, name: &str) -> Result<Address, Error> {
+        Ok(sc_address(&self.env, resolve_address(name)?))
+    }
+
+    fn unit(&self, token: &Address) -> Result<Unit, Error> {
+        let decimals: u32 = self.read(token, "decimals", ())?;
+        let symbol: soroban_sdk::String = self.read(token, "symbol", ())?;
+        Unit::new(decimals, &symbol.to_string()).map_err(Error::UnexpectedResponse)
+    }
+
+    // A write that returns nothing and takes the signer's own address first
+    fn write_as<A>(&self, signer: &str, function: &str, args: impl FnOnce(Address) -> A) -> Result<(), Error>
+    where
+        A: IntoVal<Env, SorobanVec<Val>>,
+    {
+        let signer = Signer::resolve(signer)?;
+        let me = sc_address(&self.env, signer.address());
+        self.write(&signer, function, args(me))
+    }
+
+    fn read<T: TryFromVal<Env, Val>>(
+        &self,
+        contract: &Address,
+        function: &str,
+        args: impl IntoVal<Env, SorobanVec<Val>>,
+    ) -> Result<T, Error> {
+        let call = self.host_function(contract, function, args.into_val(&self.env))?;
+        let tx = transaction(SIMULATION_SOURCE, 0, operation(call, Vec::new())?)?;
+        let simulation = self.rpc.simulate(&envelope(tx, Vec::new())?)?;
+        self.decode(&ScVal::from_xdr_base64(&simulated_call(&simulation)?.xdr, Limits::none())?)
+    }
+
+    fn write<T: TryFromVal<Env, Val>>(
+        &self,
+        signer: &Signer,
+        function: &str,
+        args: impl IntoVal<Env, SorobanVec<Val>>,
+    ) -> Result<T, Error> {
+        let call = self.host_function(&self.contract, function, args.into_val(&self.env))?;
+        let sequence = self.rpc.sequence(signer.public_key())? + 1;
+        let tx = transaction(signer.public_key(), sequence, operation(call.clone(), Vec::new())?)?;
+        let simulation = self.rpc.simulate(&envelope(tx, Vec::new())?)?;
+        let simulated = simulated_call(&simulation)?;
+
+        let mut auth = Vec::new();
+        for entry in &simulated.auth {
+            let entry = SorobanAuthorizationEntry::from_xdr_base64(entry, Limits::none())?;
+            if !matches!(entry.credentials, SorobanCredentials::SourceAccount) {
+                return Err(Error::Host(format!("{function} needs a signature from another account")));
+            }
+            auth.push(entry);
+        }
+        let missing = |field: &str| Error::UnexpectedResponse(format!("simulation returned no {field}"));
+        let data = simulation.transaction_data.as_deref().ok_or_else(|| missing("transactionData"))?;
+        let resource_fee: u32 = simulation
+            .min_resource_fee
+            .as_deref()
+            .and_then(|fee| fee.parse().ok())
+            .ok_or_else(|| missing("minResourceFee"))?;
+
+        let mut tx = transaction(signer.public_key(), sequence, operation(call, auth)?)?;
+        tx.fee = BASE_FEE.saturating_add(resource_fee);
+        tx.ext = TransactionExt::V1(SorobanTransactionData::from_xdr_base64(data, Limits::none())?);
+        let signature = signer.sign(&self.network.signature_payload(&tx)?)?;
+
+        let value = match self.rpc.send(&envelope(tx, vec![signature])?)? {
+            Some(value) => value,
+            // The simulated result stands in when the metadata can't be read
+            None => ScVal::from_xdr_base64(&simulated.xdr, Limits::none())?,
+        };
+        self.decode(&value)
+    }
+
+    fn host_function(&self, contract: &Address, function: &str, args: SorobanVec<Val>) -> Result<HostFunction, Error> {
+        let args = args
+            .iter()
+            .map(|arg| ScVal::try_from_val(&self.env, &arg))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::Host(format!("{e:?}")))?;
+        Ok(HostFunction::InvokeContract(InvokeContractArgs {
+            contract_address: contract.into(),
+            function_name: ScSymbol(function.try_into()?),
+            args: args.try_into()?,
+        }))
+    }
+
+    fn decode<T: TryFromVal<Env, Val>>(&self, value: &ScVal) -> Result<T, Error> {
+        let unexpected = || Error::UnexpectedResponse(format!("{value:?}"));
+        let value = Val::try_from_val(&self.env, value).map_err(|_| unexpected())?;
+        T::try_from_val(&self.env, &value).map_err(|_| unexpected())
+    }
+}
+
+fn operation(call: HostFunction, auth: Vec<SorobanAuthorizationEntry>) -> Result<Operation, Error> {
+    Ok(Operation {
+        source_account: None,
+        body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp { host_function: call, auth: auth.try_into()? }),
+    })
+}
+
+fn transaction(source: [u8; 32], sequence: i64, operation: Operation) -> Result<Transaction, Error> {
+    Ok(Transaction {
+        source_account: MuxedAccount::Ed25519(Uint256(source)),
+        fee: BASE_FEE,
+        seq_num: SequenceNumber(sequence),
+        cond: Preconditions::None,
+        memo: Memo::None,
+        operations: vec![operation].try_into()?,
+        ext: TransactionExt::V0,
+    })
+}
+
+fn envelope(tx: Transaction, signatures: Vec<soroban_sdk::xdr::DecoratedSignature>) -> Result<TransactionEnvelope, Error> {
+    Ok(TransactionEnvelope::Tx(TransactionV1Envelope { tx, signatures: signatures.try_into()? }))
+}
+
+fn simulated_call(simulation: &Simulation) -> Result<&SimulatedCall, Error> {
+    if let Some(error) = &simulation.error {
+        return Err(contract_error(error));
+    }
+    simulation.results.first().ok_or_else(|| Error::UnexpectedResponse("simulation returned no result".into()))
+}
+
+// Simulation reports contract failures as `Error(Contract, #13)`
+pub(crate) fn contract_error(message: &str) -> Error {
+    let code = message
+        .split("Error(Contract, #")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .and_then(|code| code.parse().ok());
+    match code.and_then(|code| ContractError::try_from(HostError::from_contract_error(code)).ok()) {
+        Some(e) => Error::Contract(e),
+        None => Error::Host(message.lines().next().unwrap_or_default().trim().to_string()),
+    }
+}
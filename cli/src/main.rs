@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::Parser;
+use safe_hands_cli::command::{Command, Outcome};
+use safe_hands_cli::error::Error;
+use safe_hands_cli::live::LiveClient;
+use safe_hands_cli::offline::OfflineLedger;
+use safe_hands_cli::rpc::Network;
+use serde_json::json;
+
+#[derive(Parser)]
+#[command(name = "safehands", about = "Operate SafeHands escrows")]
+struct Args {
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Dry run against a local ledger kept in this file instead of the network
+    #[arg(long, global = true, value_name = "LEDGER_FILE")]
+    offline: Option<PathBuf>,
+
+    /// With --offline, move the ledger clock forward this many days first
+    #[arg(long, global = true, default_value_t = 0)]
+    advance_days: u64,
+
+    /// SafeHands contract to call; required unless --offline
+    #[arg(long, global = true, env = "SAFEHANDS_CONTRACT_ID")]
+    contract: Option<String>,
+
+    /// `testnet`, `futurenet`, `mainnet`, or any name with --rpc-url and
+    /// --network-passphrase
+    #[arg(long, global = true, env = "SAFEHANDS_NETWORK", default_value = "testnet")]
+    network: String,
+
+    /// Soroban RPC server; defaults to the network's public one
+    #[arg(long, global = true, env = "SAFEHANDS_RPC_URL")]
+    rpc_url: Option<String>,
+
+    #[arg(long, global = true, env = "SAFEHANDS_NETWORK_PASSPHRASE")]
+    network_passphrase: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+fn main() {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(outcome) if args.json => println!("{}", outcome.to_json()),
+        Ok(outcome) => println!("{}", outcome.to_text()),
+        Err(e) => {
+            if args.json {
+                let code = match &e {
+                    Error::Contract(e) => Some(*e as u32),
+                    _ => None,
+                };
+                println!("{}", json!({ "error": e.to_string(), "code": code }));
+            } else {
+                eprintln!("error: {e}");
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<Outcome, Error> {
+    let Some(path) = &args.offline else {
+        let network = Network::resolve(&args.network, args.rpc_url.as_deref(), args.network_passphrase.as_deref())?;
+        let contract = args
+            .contract
+            .as_deref()
+            .ok_or_else(|| Error::Config("--contract or SAFEHANDS_CONTRACT_ID is required".into()))?;
+        return LiveClient::new(network, contract)?.run(&args.command);
+    };
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let ledger = OfflineLedger::open(path, now)?;
+    ledger.advance(args.advance_days * 86400);
+    let outcome = ledger.run(&args.command)?;
+    ledger.save()?;
+    Ok(outcome)
+}
@@ -0,0 +1,190 @@
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use safe_hands_contract::{Error as ContractError, SafeHandsContract, SafeHandsContractClient};
+use soroban_sdk::testutils::{EnvTestConfig, Ledger, Snapshot};
+use soroban_sdk::xdr::{Hash, ScAddress};
+use soroban_sdk::{token, Address, Bytes, BytesN, Env, Error as HostError, InvokeError};
+
+use crate::amount::{parse_amount, Unit};
+use crate::command::{sc_address, strkey, Command, EscrowView, Outcome};
+use crate::error::Error;
+
+// Where the dry-run contract lives in every offline ledger
+const CONTRACT_ID: [u8; 32] = [0x5a; 32];
+
+/// Dry runs against an in-process ledger. The ledger is kept in a snapshot
+/// file, so a sequence of commands can be tried out across invocations.
+/// Authorization is mocked; `fund` mints test tokens to spend.
+pub struct OfflineLedger {
+    env: Env,
+    path: PathBuf,
+    contract_id: Address,
+}
+
+impl OfflineLedger {
+    /// Opens the ledger at `path`, or sets up a fresh one starting at
+    /// `timestamp` if the file doesn't exist yet.
+    pub fn open(path: &Path, timestamp: u64) -> Result<Self, Error> {
+        let fresh = !path.exists();
+        let config = EnvTestConfig { capture_snapshot_at_drop: false };
+        let env = if fresh {
+            let env = Env::new_with_config(config);
+            env.ledger().with_mut(|li| li.timestamp = timestamp);
+            env
+        } else {
+            let bytes = fs::read(path)?;
+            let mut env = Env::from_snapshot(Snapshot::read(&bytes[..])?);
+            env.set_config(config);
+            // Mocked auth draws nonces from the PRNG; restarting it from the
+            // same seed on every reload would reuse a signer's earlier nonce
+            let seed = env.crypto().sha256(&Bytes::from_slice(&env, &bytes)).to_array();
+            env.host().set_base_prng_seed(seed).map_err(|e| Error::Host(format!("{e:?}")))?;
+            env
+        };
+        env.mock_all_auths();
+
+        let contract_id = sc_address(&env, ScAddress::Contract(Hash(CONTRACT_ID)));
+        env.register_contract(&contract_id, SafeHandsContract);
+        let ledger = OfflineLedger { env, path: path.to_path_buf(), contract_id };
+
+        if fresh {
+            let admin = ledger.address("admin")?;
+            let token = ledger.env.register_stellar_asset_contract_v2(admin.clone()).address();
+            let client = ledger.client();
+            client.initialize(&admin);
+            client.allow_token(&token, &None, &None);
+        }
+        Ok(ledger)
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        Ok(self.env.to_snapshot().write_file(&self.path)?)
+    }
+
+    /// Moves the ledger clock forward, e.g. past a deadline.
+    pub fn advance(&self, seconds: u64) {
+        self.env.ledger().with_mut(|li| li.timestamp += seconds);
+    }
+
+    pub fn run(&self, command: &Command) -> Result<Outcome, Error> {
+        let client = self.client();
+        let outcome = match command {
+            Command::Deposit { client: payer, freelancer, arbiter, amount, deadline_days, token } => {
+                let token = self.token(token.as_deref())?;
+                let amount = parse_amount(amount, &self.unit(&token)?).map_err(Error::InvalidAmount)?;
+                let id = check(client.try_deposit(
+                    &self.address(payer)?,
+                    &self.address(freelancer)?,
+                    &self.address(arbiter)?,
+                    &token,
+                    &amount,
+                    deadline_days,
+                ))?;
+                Outcome::Deposited(id)
+            }
+            Command::Accept { id, signer, terms_hash } => {
+                let terms_hash = BytesN::from_array(&self.env, terms_hash);
+                check(client.try_accept(&self.address(signer)?, id, &terms_hash))?;
+                Outcome::Done
+            }
+            Command::Approve { id, signer } => {
+                check(client.try_approve(&self.address(signer)?, id))?;
+                Outcome::Done
+            }
+            Command::Cancel { id, signer } => {
+                check(client.try_cancel(&self.address(signer)?, id))?;
+                Outcome::Done
+            }
+            Command::Dispute { id, signer } => {
+                check(client.try_dispute(&self.address(signer)?, id))?;
+                Outcome::Done
+            }
+            Command::Resolve { id, signer, winner } => {
+                check(client.try_resolve(&self.address(signer)?, id, &self.address(winner)?))?;
+                Outcome::Done
+            }
+            Command::ClaimTimeout { id, signer } => {
+                check(client.try_claim_timeout(&self.address(signer)?, id))?;
+                Outcome::Done
+            }
+            Command::Get { id } => {
+                let escrow = check(client.try_get_escrow(id))?;
+                Outcome::Escrow(Box::new(EscrowView::new(*id, &escrow, self.unit(&escrow.token)?)))
+            }
+            Command::List { user } => {
+                let mut views = Vec::new();
+                for id in client.get_user_escrows(&self.address(user)?).iter() {
+                    let escrow = client.get_escrow(&id);
+                    views.push(EscrowView::new(id, &escrow, self.unit(&escrow.token)?));
+                }
+                Outcome::Escrows(views)
+            }
+            Command::Fund { account, amount, token } => {
+                let account = self.address(account)?;
+                let token = self.token(token.as_deref())?;
+                let amount = parse_amount(amount, &self.unit(&token)?).map_err(Error::InvalidAmount)?;
+                // Accounts would need a trustline; dry-run names are contracts
+                let minted = token::StellarAssetClient::new(&self.env, &token).try_mint(&account, &amount);
+                if !matches!(minted, Ok(Ok(()))) {
+                    return Err(Error::Host(format!("cannot fund {} in a dry run; name a test account instead", strkey(&account))));
+                }
+                Outcome::Done
+            }
+        };
+        Ok(outcome)
+    }
+
+    /// A strkey, or a dry-run account named by anything else.
+    pub fn address(&self, name: &str) -> Result<Address, Error> {
+        if let Ok(address) = ScAddress::from_str(name) {
+            return Ok(sc_address(&self.env, address));
+        }
+        if name.len() == 56 && (name.starts_with('G') || name.starts_with('C')) {
+            return Err(Error::InvalidAddress(name.to_string()));
+        }
+        // Contracts, like `Address::generate`, hold tokens without a trustline
+        let key = self.env.crypto().sha256(&Bytes::from_slice(&self.env, name.as_bytes()));
+        Ok(sc_address(&self.env, ScAddress::Contract(Hash(key.to_array()))))
+    }
+
+    // The named token, or the dry-run token set up with the ledger
+    fn token(&self, name: Option<&str>) -> Result<Address, Error> {
+        match name {
+            Some(name) => self.address(name),
+            None => Ok(self.client().get_allowed_tokens().get(0).unwrap().token),
+        }
+    }
+
+    fn unit(&self, token: &Address) -> Result<Unit, Error> {
+        let token = token::Client::new(&self.env, token);
+        let decimals = check_token(token.try_decimals())?;
+        let symbol = check_token(token.try_symbol())?;
+        Unit::new(decimals, &symbol.to_string()).map_err(Error::Host)
+    }
+
+    fn client(&self) -> SafeHandsContractClient<'_> {
+        SafeHandsContractClient::new(&self.env, &self.contract_id)
+    }
+}
+
+fn check<T, E: Debug>(result: Result<Result<T, E>, Result<ContractError, InvokeError>>) -> Result<T, Error> {
+    match result {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(Error::Host(format!("{e:?}"))),
+        Err(Ok(e)) => Err(Error::Contract(e)),
+        Err(Err(e)) => Err(Error::Host(format!("{e:?}"))),
+    }
+}
+
+// Token calls fail with host errors rather than contract ones
+fn check_token<T, E: Debug>(result: Result<Result<T, E>, Result<HostError, InvokeError>>) -> Result<T, Error> {
+    match result {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(Error::Host(format!("{e:?}"))),
+        Err(Ok(e)) => Err(Error::Host(format!("{e:?}"))),
+        Err(Err(e)) => Err(Error::Host(format!("{e:?}"))),
+    }
+}
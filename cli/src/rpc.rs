@@ -0,0 +1,209 @@
+use std::thread;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use soroban_sdk::xdr::{
+    Asset, ContractIdPreimage, Hash, HashIdPreimage, HashIdPreimageContractId, LedgerEntryData, LedgerKey,
+    LedgerKeyAccount, Limits, ReadXdr, ScAddress, ScVal, Transaction, TransactionEnvelope, TransactionMeta,
+    TransactionResult, TransactionSignaturePayload, TransactionSignaturePayloadTaggedTransaction, WriteXdr,
+};
+
+use crate::error::Error;
+use crate::keys::account_address;
+
+// How long to wait for a sent transaction to land
+const CONFIRM_POLLS: u32 = 30;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where live commands go: an RPC server and the passphrase that names its
+/// network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Network {
+    pub rpc_url: String,
+    pub passphrase: String,
+}
+
+impl Network {
+    /// `testnet` and `futurenet` come with a public RPC server; any other
+    /// network needs `rpc_url`, and a name other than `mainnet` needs the
+    /// passphrase too. Either given explicitly wins over the defaults.
+    pub fn resolve(name: &str, rpc_url: Option<&str>, passphrase: Option<&str>) -> Result<Self, Error> {
+        let (default_url, default_passphrase) = match name {
+            "testnet" => (Some("https://soroban-testnet.stellar.org"), Some("Test SDF Network ; September 2015")),
+            "futurenet" => (Some("https://rpc-futurenet.stellar.org"), Some("Test SDF Future Network ; October 2022")),
+            "mainnet" => (None, Some("Public Global Stellar Network ; September 2015")),
+            _ => (None, None),
+        };
+        let rpc_url = rpc_url
+            .or(default_url)
+            .ok_or_else(|| Error::Config(format!("--rpc-url is required for network `{name}`")))?;
+        let passphrase = passphrase
+            .or(default_passphrase)
+            .ok_or_else(|| Error::Config(format!("--network-passphrase is required for network `{name}`")))?;
+        Ok(Network { rpc_url: rpc_url.to_string(), passphrase: passphrase.to_string() })
+    }
+
+    pub fn id(&self) -> Hash {
+        Hash(Sha256::digest(self.passphrase.as_bytes()).into())
+    }
+
+    /// The native asset contract (XLM) on this network.
+    pub fn native_token(&self) -> Result<ScAddress, Error> {
+        let preimage = HashIdPreimage::ContractId(HashIdPreimageContractId {
+            network_id: self.id(),
+            contract_id_preimage: ContractIdPreimage::Asset(Asset::Native),
+        });
+        Ok(ScAddress::Contract(Hash(Sha256::digest(preimage.to_xdr(Limits::none())?).into())))
+    }
+
+    /// The hash a transaction's signers sign on this network.
+    pub fn signature_payload(&self, tx: &Transaction) -> Result<[u8; 32], Error> {
+        let payload = TransactionSignaturePayload {
+            network_id: self.id(),
+            tagged_transaction: TransactionSignaturePayloadTaggedTransaction::Tx(tx.clone()),
+        };
+        Ok(Sha256::digest(payload.to_xdr(Limits::none())?).into())
+    }
+}
+
+/// A Soroban JSON-RPC server.
+pub struct Rpc {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// A `simulateTransaction` result; fields are base64 XDR.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Simulation {
+    pub error: Option<String>, // The host error the call failed with
+    pub transaction_data: Option<String>,
+    pub min_resource_fee: Option<String>,
+    #[serde(default)]
+    pub results: Vec<SimulatedCall>,
+}
+
+#[derive(Deserialize)]
+pub struct SimulatedCall {
+    #[serde(default)]
+    pub auth: Vec<String>,
+    pub xdr: String, // Return value
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entries {
+    #[serde(default)]
+    entries: Option<Vec<Entry>>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    xdr: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sent {
+    status: String,
+    hash: String,
+    error_result_xdr: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+    status: String,
+    result_meta_xdr: Option<String>,
+}
+
+impl Rpc {
+    pub fn new(url: &str) -> Self {
+        Rpc { url: url.to_string() }
+    }
+
+    /// The account's current sequence number.
+    pub fn sequence(&self, public_key: [u8; 32]) -> Result<i64, Error> {
+        let ScAddress::Account(account_id) = account_address(public_key) else { unreachable!() };
+        let key = LedgerKey::Account(LedgerKeyAccount { account_id });
+        let found: Entries = self.call("getLedgerEntries", json!({ "keys": [key.to_xdr_base64(Limits::none())?] }))?;
+        let entry = found.entries.unwrap_or_default().into_iter().next().ok_or_else(|| {
+            let address = stellar_strkey::ed25519::PublicKey(public_key).to_string();
+            Error::Host(format!("account {address} does not exist; fund it first"))
+        })?;
+        match LedgerEntryData::from_xdr_base64(&entry.xdr, Limits::none())? {
+            LedgerEntryData::Account(account) => Ok(account.seq_num.0),
+            _ => Err(Error::UnexpectedResponse("ledger entry is not an account".into())),
+        }
+    }
+
+    pub fn simulate(&self, tx: &TransactionEnvelope) -> Result<Simulation, Error> {
+        self.call("simulateTransaction", json!({ "transaction": tx.to_xdr_base64(Limits::none())? }))
+    }
+
+    /// Sends a signed transaction and waits until it is applied. Returns
+    /// the call's return value, if the server's metadata is readable.
+    pub fn send(&self, tx: &TransactionEnvelope) -> Result<Option<ScVal>, Error> {
+        let sent: Sent = self.call("sendTransaction", json!({ "transaction": tx.to_xdr_base64(Limits::none())? }))?;
+        match sent.status.as_str() {
+            "PENDING" | "DUPLICATE" => {}
+            "ERROR" => {
+                let code = sent
+                    .error_result_xdr
+                    .and_then(|xdr| TransactionResult::from_xdr_base64(xdr, Limits::none()).ok())
+                    .map(|result| format!("{:?}", result.result.discriminant()))
+                    .unwrap_or_default();
+                return Err(Error::Host(format!("transaction {} rejected {code}", sent.hash)));
+            }
+            status => return Err(Error::Host(format!("transaction {} not accepted: {status}", sent.hash))),
+        }
+
+        for _ in 0..CONFIRM_POLLS {
+            thread::sleep(POLL_INTERVAL);
+            let status: Status = self.call("getTransaction", json!({ "hash": sent.hash }))?;
+            match status.status.as_str() {
+                "NOT_FOUND" => continue,
+                "SUCCESS" => {
+                    // Newer servers send metadata this XDR version can't read
+                    let meta = status.result_meta_xdr.and_then(|xdr| TransactionMeta::from_xdr_base64(xdr, Limits::none()).ok());
+                    return Ok(match meta {
+                        Some(TransactionMeta::V3(meta)) => meta.soroban_meta.map(|soroban| soroban.return_value),
+                        _ => None,
+                    });
+                }
+                status => return Err(Error::Host(format!("transaction {} {}", sent.hash, status.to_lowercase()))),
+            }
+        }
+        Err(Error::Host(format!("transaction {} not confirmed after {CONFIRM_POLLS}s", sent.hash)))
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let text = ureq::post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .map_err(|e| Error::Http(e.to_string()))?
+            .into_string()?;
+
+        let response: Response<T> = serde_json::from_str(&text)?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(Error::Rpc(error.code, error.message)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(Error::Rpc(0, format!("{method} returned no result"))),
+        }
+    }
+}
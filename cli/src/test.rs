@@ -0,0 +1,235 @@
+extern crate std;
+
+use std::path::PathBuf;
+
+use safe_hands_contract::Error as ContractError;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use soroban_sdk::xdr::ScAddress;
+
+use crate::amount::{format_amount, parse_amount, Unit};
+use crate::command::{hash_hex, parse_hash, Command, Outcome};
+use crate::error::Error;
+use crate::keys::{resolve_address, Signer};
+use crate::live::contract_error;
+use crate::offline::OfflineLedger;
+use crate::rpc::Network;
+
+const START: u64 = 1_700_000_000;
+
+fn ledger_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("safehands-{}-{name}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn deposit(amount: &str, deadline_days: u64) -> Command {
+    Command::Deposit {
+        client: "alice".into(),
+        freelancer: "bob".into(),
+        arbiter: "carol".into(),
+        amount: amount.into(),
+        deadline_days,
+        token: None,
+    }
+}
+
+fn fund(account: &str, amount: &str) -> Command {
+    Command::Fund { account: account.into(), amount: amount.into(), token: None }
+}
+
+fn state(ledger: &OfflineLedger, id: u64) -> String {
+    match ledger.run(&Command::Get { id }).unwrap() {
+        Outcome::Escrow(view) => view.state,
+        outcome => panic!("unexpected {outcome:?}"),
+    }
+}
+
+#[test]
+fn test_amounts() {
+    let xlm = Unit::new(7, "native").unwrap();
+    assert_eq!(xlm.symbol, "XLM");
+    assert_eq!(parse_amount("25", &xlm), Ok(250_000_000));
+    assert_eq!(parse_amount("2.5xlm", &xlm), Ok(25_000_000));
+    assert_eq!(parse_amount("2.5 XLM", &xlm), Ok(25_000_000));
+    assert_eq!(parse_amount(".0000001", &xlm), Ok(1));
+    assert_eq!(parse_amount("25000000stroops", &xlm), Ok(25_000_000));
+
+    assert!(parse_amount("1.12345678", &xlm).unwrap_err().contains("more than 7 decimals"));
+    assert!(parse_amount("0", &xlm).is_err());
+    assert!(parse_amount("0stroops", &xlm).is_err());
+    assert!(parse_amount("-1", &xlm).is_err());
+    assert!(parse_amount("1.5.2", &xlm).is_err());
+    assert!(parse_amount("xlm", &xlm).is_err());
+    assert!(parse_amount("2.5usdc", &xlm).is_err());
+
+    assert_eq!(format_amount(15_000_000, &xlm), "1.50 XLM");
+    assert_eq!(format_amount(250_000_000, &xlm), "25.00 XLM");
+    assert_eq!(format_amount(5, &xlm), "0.0000005 XLM");
+    assert_eq!(format_amount(-15_000_000, &xlm), "-1.50 XLM");
+
+    // Scaled by the token's own decimals
+    let usdc = Unit::new(2, "USDC").unwrap();
+    assert_eq!(parse_amount("2.5usdc", &usdc), Ok(250));
+    assert!(parse_amount("2.505", &usdc).unwrap_err().contains("more than 2 decimals"));
+    assert_eq!(format_amount(250, &usdc), "2.50 USDC");
+    assert_eq!(format_amount(7, &usdc), "0.07 USDC");
+
+    let points = Unit::new(0, "PTS").unwrap();
+    assert_eq!(parse_amount("40", &points), Ok(40));
+    assert!(parse_amount("4.5", &points).is_err());
+    assert_eq!(format_amount(40, &points), "40 PTS");
+
+    assert!(Unit::new(39, "BIG").is_err());
+}
+
+#[test]
+fn test_offline_flow() {
+    let path = ledger_path("flow");
+    let ledger = OfflineLedger::open(&path, START).unwrap();
+
+    // Deposits spend funds the client was given first
+    assert!(ledger.run(&deposit("2.5", 30)).is_err());
+    ledger.run(&fund("alice", "2.5")).unwrap();
+    assert_eq!(ledger.run(&deposit("2.5", 30)).unwrap(), Outcome::Deposited(0));
+    ledger.save().unwrap();
+    drop(ledger);
+
+    // Reopened from the file, the escrow is still there
+    let ledger = OfflineLedger::open(&path, 0).unwrap();
+    let Outcome::Escrow(view) = ledger.run(&Command::Get { id: 0 }).unwrap() else { panic!() };
+    assert_eq!(view.state, "Funded");
+    assert_eq!(view.amount, 25_000_000);
+    assert_eq!(view.locked_amount, 25_000_000);
+    assert_eq!(view.deadline, START + 30 * 86400);
+    assert_eq!(view.terms_hash, "00".repeat(32));
+    assert_eq!(view.unit, Unit::new(7, "aaa").unwrap());
+    assert_eq!(view.client, crate::command::strkey(&ledger.address("alice").unwrap()));
+
+    ledger.run(&Command::Dispute { id: 0, signer: "alice".into() }).unwrap();
+    ledger
+        .run(&Command::Resolve { id: 0, signer: "carol".into(), winner: "bob".into() })
+        .unwrap();
+    assert_eq!(state(&ledger, 0), "Resolved");
+
+    let Outcome::Escrows(views) = ledger.run(&Command::List { user: "bob".into() }).unwrap() else { panic!() };
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].released_amount, 25_000_000);
+
+    // Contract errors come through typed
+    let result = ledger.run(&Command::Approve { id: 0, signer: "bob".into() });
+    assert!(matches!(result, Err(Error::Contract(ContractError::NotApprovable))));
+    let result = ledger.run(&Command::Get { id: 7 });
+    assert!(matches!(result, Err(Error::Contract(ContractError::EscrowNotFound))));
+
+    // Account strkeys have no trustline to be funded through
+    let account = "GAV5QBWJP4HABLY2D7BTFD5HMOUSNFZDZDNY7LCPSOXXDWYYNVXJBKNV";
+    let result = ledger.run(&fund(account, "1"));
+    assert!(matches!(result, Err(Error::Host(_))));
+    assert!(matches!(ledger.run(&fund("dave", "1.5.2")), Err(Error::InvalidAmount(_))));
+    assert!(matches!(ledger.address(&account.replace("B", "A")), Err(Error::InvalidAddress(_))));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_offline_release() {
+    let path = ledger_path("release");
+    // Each command on a freshly opened ledger, as separate invocations run
+    let run = |command: Command| -> Result<Outcome, Error> {
+        let ledger = OfflineLedger::open(&path, START)?;
+        let outcome = ledger.run(&command)?;
+        ledger.save()?;
+        Ok(outcome)
+    };
+    let approve = |signer: &str| Command::Approve { id: 0, signer: signer.into() };
+    let accept = |terms_hash| Command::Accept { id: 0, signer: "bob".into(), terms_hash };
+    let state = || state(&OfflineLedger::open(&path, 0).unwrap(), 0);
+
+    run(fund("alice", "2.5")).unwrap();
+    run(deposit("25000000stroops", 30)).unwrap();
+
+    // Delivery can't be approved before the freelancer takes the job
+    assert!(matches!(run(approve("alice")), Err(Error::Contract(ContractError::NotAccepted))));
+    assert!(matches!(run(accept([1; 32])), Err(Error::Contract(ContractError::TermsMismatch))));
+
+    run(accept([0; 32])).unwrap();
+    assert_eq!(state(), "Accepted");
+    run(approve("alice")).unwrap();
+    assert_eq!(state(), "Accepted");
+    run(approve("bob")).unwrap();
+
+    let Outcome::Escrow(view) = run(Command::Get { id: 0 }).unwrap() else { panic!() };
+    assert_eq!(view.state, "Released");
+    assert_eq!(view.released_amount, 25_000_000);
+    assert_eq!(view.locked_amount, 0);
+
+    // `--terms-hash` takes hex
+    let hash = [0xab; 32];
+    assert_eq!(parse_hash(&hash_hex(&hash)), Ok(hash));
+    assert_eq!(parse_hash(&format!("0x{}", "00".repeat(32))), Ok([0; 32]));
+    assert!(parse_hash("abcd").is_err());
+    assert!(parse_hash(&"zz".repeat(32)).is_err());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_offline_timeout() {
+    let path = ledger_path("timeout");
+    let ledger = OfflineLedger::open(&path, START).unwrap();
+    ledger.run(&fund("alice", "5stroops")).unwrap();
+    ledger.run(&deposit("5stroops", 1)).unwrap();
+
+    let claim = Command::ClaimTimeout { id: 0, signer: "dave".into() };
+    assert!(matches!(ledger.run(&claim), Err(Error::Contract(_))));
+    assert_eq!(state(&ledger, 0), "Funded");
+
+    ledger.advance(2 * 86400);
+    ledger.run(&claim).unwrap();
+    assert_eq!(state(&ledger, 0), "Cancelled");
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_simulation_errors() {
+    let error = "HostError: Error(Contract, #6)\n\nEvent log (newest first):\n   0: ...";
+    assert!(matches!(contract_error(error), Error::Contract(ContractError::EscrowNotFound)));
+
+    let error = "HostError: Error(Storage, MissingValue)\n\nEvent log (newest first):\n   0: ...";
+    assert!(matches!(contract_error(error), Error::Host(e) if e == "HostError: Error(Storage, MissingValue)"));
+}
+
+#[test]
+fn test_networks() {
+    let testnet = Network::resolve("testnet", None, None).unwrap();
+    assert_eq!(testnet.rpc_url, "https://soroban-testnet.stellar.org");
+    let native = testnet.native_token().unwrap();
+    assert_eq!(native.to_string(), "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC");
+
+    let local = Network::resolve("local", Some("http://localhost:8000/soroban/rpc"), Some("Standalone Network ; February 2017"));
+    assert_eq!(local.unwrap().passphrase, "Standalone Network ; February 2017");
+    assert!(matches!(Network::resolve("mainnet", None, None), Err(Error::Config(_))));
+    assert!(matches!(Network::resolve("local", Some("http://localhost:8000"), None), Err(Error::Config(_))));
+}
+
+#[test]
+fn test_signer() {
+    let secret = stellar_strkey::ed25519::PrivateKey([7; 32]).to_string();
+    let signer = Signer::resolve(&secret).unwrap();
+    let ScAddress::Account(_) = signer.address() else { panic!() };
+
+    let hash = [0xab; 32];
+    let signature = signer.sign(&hash).unwrap();
+    assert_eq!(signature.hint.0, signer.public_key()[28..]);
+    let key = VerifyingKey::from_bytes(&signer.public_key()).unwrap();
+    let signature = Signature::from_slice(&signature.signature.0).unwrap();
+    assert!(key.verify(&hash, &signature).is_ok());
+
+    // The signer's account stands for itself as an address argument
+    let account = signer.address().to_string();
+    assert_eq!(resolve_address(&account).unwrap(), signer.address());
+    assert!(matches!(Signer::resolve(&account), Err(Error::InvalidKey(_))));
+    assert!(matches!(Signer::resolve("no-such-identity"), Err(Error::InvalidKey(_))));
+}
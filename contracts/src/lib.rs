@@ -1,61 +1,1687 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, symbol_short, Vec};
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, token, Address, Bytes, BytesN, Env, Map, String, symbol_short, Vec};
+
+#[contracterror]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    AmountMustBePositive = 1,
+    DeadlineTooShort = 2,
+    NoMilestones = 3,
+    AmountOverflow = 4,
+    EscrowIdLimitReached = 5,
+    EscrowNotFound = 6,
+    NotApprovable = 7,
+    DeadlinePassed = 8,
+    NoMilestoneLeft = 9,
+    NotAuthorizedToApprove = 10,
+    OnlyClientCanCancel = 11,
+    NotCancellable = 12,
+    FreelancerAlreadyAccepted = 13,
+    OnlyPartiesCanDispute = 14,
+    NotDisputable = 15,
+    NotArbiter = 16,
+    NotResolvable = 17,
+    InvalidWinner = 18,
+    NotClaimable = 19,
+    DeadlineNotPassed = 20,
+    InvalidSplit = 21,
+    InvalidFee = 22,
+    AlreadyInitialized = 23,
+    NotInitialized = 24,
+    NoPendingAdmin = 25,
+    MigrationPending = 26,
+    ContractPaused = 27,
+    TokenNotAllowed = 28,
+    AmountBelowMinimum = 29,
+    AmountAboveMaximum = 30,
+    InvalidTokenLimits = 31,
+    InvalidPanel = 32,
+    PanelVoteRequired = 33,
+    NotDisputed = 34,
+    EvidenceLimitReached = 35,
+    InvalidEvidence = 36,
+    NotParty = 37,
+    ArbitrationWindowOpen = 38,
+    NoPendingAmendment = 39,
+    InvalidAmendment = 40,
+    TermsMismatch = 41,
+    InvalidTermsUri = 42,
+    NotAccepted = 43,
+    NotFreelancer = 44,
+    NoSettlementOffer = 45,
+    InvalidSettlement = 46,
+    InvalidVesting = 47,
+    NotStream = 48,
+    NothingToWithdraw = 49,
+    InvalidBatch = 50,
+}
 
 #[contracttype]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum EscrowState {
-    Funded = 0,
+    Funded = 0, // Offered to the freelancer, not yet accepted
     Released = 1,
     Cancelled = 2,
     Disputed = 3,
     Resolved = 4,
+    Accepted = 5, // Freelancer took the job; work in progress
+    Settled = 6, // Dispute closed by a settlement the parties agreed on
+}
+
+/// How the arbiter divides the locked amount in `resolve_split`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Split {
+    Bps(u32), // Freelancer's share in basis points, the rest goes to the client
+    Amounts(i128, i128), // (client, freelancer), must add up to the locked amount minus the arbiter fee
+}
+
+/// What happens to the locked amount if the arbiter doesn't decide a
+/// dispute within `ARBITRATION_WINDOW`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fallback {
+    Refund, // Everything back to the client
+    Release, // Everything to the freelancer
+    Split(u32), // Freelancer's share in basis points, the rest to the client
+}
+
+/// Linear vesting schedule for streaming escrows. Nothing vests before
+/// `cliff`; from then on the amount vested is proportional to the time
+/// elapsed since `start`, reaching the full amount at `end`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Vesting {
+    pub start: u64,
+    pub cliff: u64, // Equal to `start` for a plain linear stream
+    pub end: u64,
+}
+
+/// How an escrow pays the freelancer.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EscrowKind {
+    LumpSum, // Milestones released by mutual approval
+    Stream(Vesting), // Vests over time; paid through `withdraw_vested`
+}
+
+/// Job terms and dispute settings for `deposit_with_options`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowOptions {
+    pub terms_hash: BytesN<32>, // SHA-256 of the agreed job terms
+    pub terms_uri: Option<Bytes>, // Where the terms document can be fetched
+    pub panel: Vec<Address>, // A single arbiter, or a panel that decides by vote
+    pub threshold: u32, // Matching panel votes needed to settle
+    pub fallback: Fallback,
+    pub kind: EscrowKind,
+    pub keeper_bounty: i128, // Paid on top of the amount; 0 for none
+}
+
+/// Fees charged by the platform. Rates and treasury are copied onto each
+/// escrow at `deposit`, so changing them never affects existing jobs.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeConfig {
+    pub treasury: Address,
+    pub platform_fee_bps: u32, // Taken from every release to the freelancer
+    pub arbiter_fee_bps: u32, // Taken from the locked amount when the arbiter resolves
+}
+
+/// Deposit bounds for an accepted token. `None` means no bound.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TokenLimits {
+    pub min_amount: Option<i128>,
+    pub max_amount: Option<i128>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowedToken {
+    pub token: Address,
+    pub limits: TokenLimits,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    Client = 0,
+    Freelancer = 1,
+    Arbiter = 2,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowEntry {
+    pub id: u64,
+    pub escrow: Escrow,
+}
+
+/// One escrow in a `deposit_batch` call.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchDeposit {
+    pub freelancer: Address,
+    pub arbiter: Address,
+    pub amount: i128,
+    pub deadline_days: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EscrowPage {
+    pub escrows: Vec<EscrowEntry>,
+    pub next_cursor: Option<u32>, // Pass back as `cursor` to continue, `None` when done
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Vote {
+    pub arbiter: Address,
+    pub outcome: Split,
+    pub timestamp: u64,
+}
+
+/// A proposed change to an escrow's deadline and/or unreleased milestones,
+/// waiting for the other party to accept.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Amendment {
+    pub proposer: Address,
+    pub deadline: u64,
+    pub amounts: Vec<i128>, // Replaces every unreleased milestone
+    pub from_milestone: u32, // First unreleased milestone when proposed
+    pub top_up: i128, // Already paid in by a client proposing an increase
+}
+
+/// A split of a disputed escrow's locked amount offered by one party.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SettlementOffer {
+    pub proposer: Address,
+    pub split: Split,
+    pub locked_amount: i128, // Locked amount the split was offered against
+}
+
+/// A content hash (e.g. an IPFS CID or the SHA-256 of a file) submitted
+/// by one of the parties while the escrow is disputed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Evidence {
+    pub submitter: Address,
+    pub hash: Bytes,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Milestone {
+    pub amount: i128,
+    pub approved_by_client: bool,
+    pub approved_by_freelancer: bool,
+    pub released: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Escrow {
+    pub client: Address,
+    pub freelancer: Address,
+    pub arbiter: Address, // Sole arbiter, or first member of the panel
+    pub panel: Vec<Address>, // Arbiters who may decide a dispute; just `arbiter` unless a panel was named
+    pub threshold: u32, // Matching panel votes needed to settle
+    pub amount: i128, // Total of all milestones
+    pub token: Address,
+    pub milestones: Vec<Milestone>, // Released in order, lowest index first
+    pub released_amount: i128, // Paid out to the freelancer so far
+    pub locked_amount: i128, // Still held by the contract
+    pub fees_paid: i128, // Platform and arbiter fees paid out so far
+    pub platform_fee_bps: u32,
+    pub arbiter_fee_bps: u32,
+    pub treasury: Option<Address>, // Receives the platform fee; `None` if no fees were configured
+    pub state: EscrowState,
+    pub deadline: u64, // Ledger timestamp after which client can reclaim funds
+    pub fallback: Fallback, // Applied if the arbiter misses the arbitration window
+    pub arbitration_deadline: u64, // Set on dispute; 0 while not disputed
+    pub version: u32, // Number of accepted amendments
+    pub terms_hash: BytesN<32>, // All zeroes if no terms were anchored at deposit
+    pub terms_uri: Option<Bytes>,
+    pub accepted_at: Option<u64>, // When the freelancer accepted; `None` until then
+    pub kind: EscrowKind,
+    pub keeper_bounty: i128, // Held apart from `locked_amount`; paid to whoever claims the timeout
+}
+
+/// Fields shared by every escrow event: the escrow as it stands once the
+/// transition has been applied. Replaying an escrow's events in order gives
+/// its full history.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EventHeader {
+    pub schema: u32, // EVENT_VERSION at the time of emission
+    pub escrow_id: u64,
+    pub state: EscrowState,
+    pub deadline: u64,
+    pub token: Address,
+    pub locked_amount: i128,
+    pub released_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositEvent {
+    pub header: EventHeader,
+    pub client: Address,
+    pub freelancer: Address,
+    pub amount: i128,
+    pub keeper_bounty: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AcceptEvent {
+    pub header: EventHeader,
+    pub freelancer: Address,
+    pub terms_hash: BytesN<32>,
+}
+
+/// One party's approval of a milestone; followed by a `ReleaseEvent` once
+/// both have approved.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApproveEvent {
+    pub header: EventHeader,
+    pub approver: Address,
+    pub milestone: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReleaseEvent {
+    pub header: EventHeader,
+    pub milestone: u32,
+    pub paid: i128, // Net of the platform fee
+    pub platform_fee: i128,
+}
+
+/// Emitted by `cancel` and `decline`; both refund the client.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RefundEvent {
+    pub header: EventHeader,
+    pub caller: Address,
+    pub refund: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawEvent {
+    pub header: EventHeader,
+    pub paid: i128,
+    pub platform_fee: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StopEvent {
+    pub header: EventHeader,
+    pub refund: i128,
+    pub paid: i128,
+    pub platform_fee: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProposalEvent {
+    pub header: EventHeader,
+    pub proposer: Address,
+    pub deadline: u64,
+    pub increase: i128, // Negative for a decrease
+    pub top_up: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProposalWithdrawnEvent {
+    pub header: EventHeader,
+    pub proposer: Address,
+    pub refund: i128, // Top-up returned to the proposer
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AmendEvent {
+    pub header: EventHeader,
+    pub acceptor: Address,
+    pub version: u32,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisputeEvent {
+    pub header: EventHeader,
+    pub caller: Address,
+    pub arbitration_deadline: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EvidenceEvent {
+    pub header: EventHeader,
+    pub submitter: Address,
+    pub hash: Bytes,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OfferEvent {
+    pub header: EventHeader,
+    pub proposer: Address,
+    pub split: Split,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VoteEvent {
+    pub header: EventHeader,
+    pub arbiter: Address,
+    pub outcome: Split,
+}
+
+/// How a disputed escrow's locked amount was paid out. Emitted by
+/// `accept_settlement`, the arbiter's decision and the timeout fallback;
+/// `arbiter` is `None` when no arbiter decided.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PayoutEvent {
+    pub header: EventHeader,
+    pub arbiter: Option<Address>,
+    pub client_amount: i128,
+    pub freelancer_paid: i128,
+    pub arbiter_fee: i128,
+    pub platform_fee: i128,
 }
 
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Escrow {
-    pub client: Address,
-    pub freelancer: Address,
-    pub arbiter: Address,
-    pub amount: i128,
-    pub token: Address,
-    pub approved_by_client: bool,
-    pub approved_by_freelancer: bool,
-    pub state: EscrowState,
-    pub deadline: u64, // Ledger timestamp after which client can reclaim funds
-}
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimeoutEvent {
+    pub header: EventHeader,
+    pub keeper: Address,
+    pub refund: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BountyEvent {
+    pub header: EventHeader,
+    pub keeper: Address,
+    pub bounty: i128,
+}
+
+// Ledgers close roughly every 5 seconds
+const LEDGER_CLOSE_SECONDS: u64 = 5;
+const DAY_IN_LEDGERS: u32 = 17280;
+
+const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+// Escrows stay live this long past their deadline so a timeout can still be claimed
+const ESCROW_TTL_BUFFER: u32 = 30 * DAY_IN_LEDGERS;
+const USER_BUMP_AMOUNT: u32 = 90 * DAY_IN_LEDGERS;
+
+// While paused, clients may still withdraw via cancel/claim_timeout after this long
+const PAUSE_GRACE_PERIOD: u64 = 7 * 86400;
+
+// Once disputed, the deadline stops counting and the arbiter has this long
+// to decide before the escrow's fallback outcome becomes claimable
+const ARBITRATION_WINDOW: u64 = 14 * 86400;
+
+// Bump when the layout of stored data changes and add a step to `migrate`
+const STORAGE_VERSION: u32 = 1;
+
+// Account allowed to call `initialize`, baked in at build time
+// (`SAFE_HANDS_DEPLOYER=G... cargo build --target wasm32-unknown-unknown`)
+// so nobody can claim the admin role between deploy and the operator's own
+// call. A contract build without it fails rather than deploying a contract
+// no one can initialize.
+#[cfg(target_family = "wasm")]
+const DEPLOYER: &str = env!("SAFE_HANDS_DEPLOYER");
+// Native builds (tests and tools linking the contract) only run it with
+// mocked auth
+#[cfg(not(target_family = "wasm"))]
+const DEPLOYER: &str = match option_env!("SAFE_HANDS_DEPLOYER") {
+    Some(deployer) => deployer,
+    None => "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF",
+};
+
+// Bump when the layout of an event struct changes
+pub const EVENT_VERSION: u32 = 1;
+
+// Paginated queries return at most MAX_PAGE_SIZE escrows and read at most
+// MAX_PAGE_SCAN index entries per call, whatever the filter
+const MAX_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SCAN: u32 = 200;
+
+const MAX_PANEL_SIZE: u32 = 9;
+
+const MAX_BATCH_SIZE: u32 = 25;
+
+const MAX_EVIDENCE_PER_PARTY: u32 = 10;
+const MAX_EVIDENCE_HASH_LEN: u32 = 128; // Fits CIDv1 strings as well as raw digests
+const MAX_TERMS_URI_LEN: u32 = 256;
+
+const BPS_DENOMINATOR: i128 = 10_000;
+const MAX_FEE_BPS: u32 = 1_000;
+
+#[contracttype]
+pub enum DataKey {
+    NextEscrowId,
+    Escrow(u64),
+    UserEscrows(Address),
+    FeeConfig,
+    Admin,
+    PendingAdmin,
+    StorageVersion,
+    PausedAt,
+    AllowedTokens,
+    ArbiterQueue(Address), // Disputed escrows awaiting this arbiter
+    Votes(u64),
+    Evidence(u64),
+    Amendment(u64),
+    Settlement(u64),
+    ExpiryBucket(u64), // Open/disputed escrows that become claimable on this day
+    ExpiryDays, // Sorted days with a non-empty bucket
+}
+
+#[contract]
+pub struct SafeHandsContract;
+
+#[contractimpl]
+impl SafeHandsContract {
+
+    pub fn deposit(
+        env: Env,
+        client: Address,
+        freelancer: Address,
+        arbiter: Address,
+        token: Address,
+        amount: i128,
+        deadline_days: u64,
+    ) -> Result<u64, Error> {
+        let mut amounts = Vec::new(&env);
+        amounts.push_back(amount);
+        Self::deposit_milestones(env, client, freelancer, arbiter, token, amounts, deadline_days)
+    }
+
+    /// Deposit an escrow split into ordered milestones. Each milestone is
+    /// approved and released on its own; the escrow is `Released` once the
+    /// last one has been paid out.
+    pub fn deposit_milestones(
+        env: Env,
+        client: Address,
+        freelancer: Address,
+        arbiter: Address,
+        token: Address,
+        amounts: Vec<i128>,
+        deadline_days: u64,
+    ) -> Result<u64, Error> {
+        let mut panel = Vec::new(&env);
+        panel.push_back(arbiter);
+        let options = Self::basic_options(&env, panel, 1);
+        Self::create_escrow(env, client, freelancer, token, amounts, deadline_days, options)
+    }
+
+    /// Deposit an escrow whose disputes are decided by a panel of arbiters:
+    /// `threshold` members must vote for the same outcome (see `vote`).
+    #[allow(clippy::too_many_arguments)]
+    pub fn deposit_with_panel(
+        env: Env,
+        client: Address,
+        freelancer: Address,
+        panel: Vec<Address>,
+        threshold: u32,
+        token: Address,
+        amounts: Vec<i128>,
+        deadline_days: u64,
+    ) -> Result<u64, Error> {
+        let options = Self::basic_options(&env, panel, threshold);
+        Self::create_escrow(env, client, freelancer, token, amounts, deadline_days, options)
+    }
+
+    /// Create one single-milestone escrow per entry, collecting the total
+    /// from the client in one transfer. All-or-nothing: if any entry is
+    /// invalid, no escrow is created. Returns the new IDs in entry order.
+    pub fn deposit_batch(
+        env: Env,
+        client: Address,
+        token: Address,
+        entries: Vec<BatchDeposit>,
+    ) -> Result<Vec<u64>, Error> {
+        client.require_auth();
+        Self::ensure_not_paused(&env)?;
+        Self::check_batch_size(entries.len())?;
+
+        let mut ids = Vec::new(&env);
+        let mut total: i128 = 0;
+        for entry in entries.iter() {
+            let mut panel = Vec::new(&env);
+            panel.push_back(entry.arbiter);
+            let mut amounts = Vec::new(&env);
+            amounts.push_back(entry.amount);
+            let options = Self::basic_options(&env, panel, 1);
+
+            let (escrow_id, amount) = Self::open_escrow(
+                env.clone(), client.clone(), entry.freelancer, token.clone(), amounts, entry.deadline_days, options,
+            )?;
+            total = total.checked_add(amount).ok_or(Error::AmountOverflow)?;
+            ids.push_back(escrow_id);
+        }
+
+        let token_client = token::Client::new(&env, &token);
+        token_client.transfer(&client, &env.current_contract_address(), &total);
+
+        Ok(ids)
+    }
+
+    /// Deposit with the job terms anchored on-chain and explicit dispute
+    /// settings: arbiter panel and the fallback outcome if arbitration
+    /// times out.
+    pub fn deposit_with_options(
+        env: Env,
+        client: Address,
+        freelancer: Address,
+        token: Address,
+        amounts: Vec<i128>,
+        deadline_days: u64,
+        options: EscrowOptions,
+    ) -> Result<u64, Error> {
+        Self::create_escrow(env, client, freelancer, token, amounts, deadline_days, options)
+    }
+
+    /// Freelancer takes the job, committing to the exact terms the client
+    /// anchored at deposit. Moves the escrow from `Funded` to `Accepted`;
+    /// from then on the client can no longer cancel and delivery can be
+    /// approved.
+    pub fn accept(env: Env, freelancer: Address, escrow_id: u64, terms_hash: BytesN<32>) -> Result<(), Error> {
+        freelancer.require_auth();
+        Self::extend_instance(&env);
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        if freelancer != escrow.freelancer {
+            return Err(Error::NotFreelancer);
+        }
+
+        if escrow.state != EscrowState::Funded {
+            return Err(Error::NotApprovable);
+        }
+
+        if terms_hash != escrow.terms_hash {
+            return Err(Error::TermsMismatch);
+        }
+
+        // An accepted stream pays out by vesting and can no longer time out
+        if escrow.kind != EscrowKind::LumpSum {
+            Self::unindex_expiry(&env, escrow_id, &escrow);
+        }
+        escrow.state = EscrowState::Accepted;
+        escrow.accepted_at = Some(env.ledger().timestamp());
+        Self::save_escrow(&env, escrow_id, &escrow);
+
+        env.events().publish(
+            (symbol_short!("accept"), freelancer.clone()),
+            AcceptEvent { header: Self::event_header(escrow_id, &escrow), freelancer, terms_hash },
+        );
+
+        Ok(())
+    }
+
+    /// Approve the current (lowest unreleased) milestone. Once both parties
+    /// have approved it, that milestone is paid out to the freelancer.
+    pub fn approve(env: Env, approver: Address, escrow_id: u64) -> Result<(), Error> {
+        approver.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        Self::approve_escrow(&env, &approver, escrow_id)
+    }
+
+    /// Approve the current milestone of every escrow in `escrow_ids` in one
+    /// call. All-or-nothing: if any approval fails, none is applied.
+    pub fn approve_batch(env: Env, approver: Address, escrow_ids: Vec<u64>) -> Result<(), Error> {
+        approver.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        Self::check_batch_size(escrow_ids.len())?;
+
+        for escrow_id in escrow_ids.iter() {
+            Self::approve_escrow(&env, &approver, escrow_id)?;
+        }
+        Ok(())
+    }
+
+    /// Cancel a job the freelancer hasn't accepted yet and refund it to the
+    /// client.
+    pub fn cancel(env: Env, caller: Address, escrow_id: u64) -> Result<(), Error> {
+        caller.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_refunds_allowed(&env)?;
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        
+        if caller != escrow.client {
+             return Err(Error::OnlyClientCanCancel);
+        }
+
+        if escrow.state == EscrowState::Accepted {
+             return Err(Error::FreelancerAlreadyAccepted);
+        }
+
+        if escrow.state != EscrowState::Funded {
+             return Err(Error::NotCancellable);
+        }
+
+        // Refund
+        let refund = escrow.locked_amount;
+        let token_client = token::Client::new(&env, &escrow.token);
+        token_client.transfer(
+            &env.current_contract_address(),
+            &escrow.client,
+            &refund,
+        );
+        
+        escrow.locked_amount = 0;
+        Self::close_escrow(&env, escrow_id, &mut escrow, EscrowState::Cancelled);
+        Self::save_escrow(&env, escrow_id, &escrow);
+
+        env.events().publish(
+            (symbol_short!("cancel"), escrow.client.clone()),
+            RefundEvent { header: Self::event_header(escrow_id, &escrow), caller, refund },
+        );
+
+        Ok(())
+    }
+
+    /// Freelancer walks away from the job: everything still locked goes back
+    /// to the client and the escrow closes as `Cancelled`. Allowed at any
+    /// point before release, including during a dispute.
+    pub fn decline(env: Env, freelancer: Address, escrow_id: u64) -> Result<(), Error> {
+        freelancer.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_refunds_allowed(&env)?;
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        if freelancer != escrow.freelancer {
+             return Err(Error::NotFreelancer);
+        }
+
+        if escrow.state != EscrowState::Disputed && !Self::is_open(&escrow) {
+             return Err(Error::NotCancellable);
+        }
+
+        let refund = escrow.locked_amount;
+        let token_client = token::Client::new(&env, &escrow.token);
+        token_client.transfer(
+            &env.current_contract_address(),
+            &escrow.client,
+            &refund,
+        );
+
+        escrow.locked_amount = 0;
+        Self::close_escrow(&env, escrow_id, &mut escrow, EscrowState::Cancelled);
+        Self::save_escrow(&env, escrow_id, &escrow);
+
+        env.events().publish(
+            (symbol_short!("decline"), freelancer.clone(), escrow.client.clone()),
+            RefundEvent { header: Self::event_header(escrow_id, &escrow), caller: freelancer, refund },
+        );
+
+        Ok(())
+    }
+
+    /// Pay the freelancer everything vested so far on an accepted stream,
+    /// less what was already withdrawn. Returns the amount paid out.
+    pub fn withdraw_vested(env: Env, freelancer: Address, escrow_id: u64) -> Result<i128, Error> {
+        freelancer.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        let EscrowKind::Stream(schedule) = escrow.kind.clone() else {
+            return Err(Error::NotStream);
+        };
+
+        if freelancer != escrow.freelancer {
+            return Err(Error::NotFreelancer);
+        }
+
+        if escrow.state == EscrowState::Funded {
+            return Err(Error::NotAccepted);
+        }
+
+        if escrow.state != EscrowState::Accepted {
+            return Err(Error::NotApprovable);
+        }
+
+        let withdrawable = Self::vested_amount(&env, &escrow, &schedule) - (escrow.amount - escrow.locked_amount);
+        if withdrawable <= 0 {
+            return Err(Error::NothingToWithdraw);
+        }
+
+        let (paid, platform_fee) = Self::release_to_freelancer(&env, &mut escrow, withdrawable);
+        escrow.locked_amount -= withdrawable;
+        if escrow.locked_amount == 0 {
+            let mut milestone = escrow.milestones.get_unchecked(0);
+            milestone.released = true;
+            escrow.milestones.set(0, milestone);
+            Self::close_escrow(&env, escrow_id, &mut escrow, EscrowState::Released);
+        }
+        Self::save_escrow(&env, escrow_id, &escrow);
+
+        env.events().publish(
+            (symbol_short!("withdraw"), escrow.client.clone(), freelancer),
+            WithdrawEvent { header: Self::event_header(escrow_id, &escrow), paid, platform_fee },
+        );
+
+        Ok(paid)
+    }
+
+    /// Client stops a stream: the freelancer is paid what has vested, the
+    /// unvested remainder goes back to the client, and the escrow closes as
+    /// `Cancelled`.
+    pub fn stop_stream(env: Env, client: Address, escrow_id: u64) -> Result<(), Error> {
+        client.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        let EscrowKind::Stream(schedule) = escrow.kind.clone() else {
+            return Err(Error::NotStream);
+        };
+
+        if client != escrow.client {
+            return Err(Error::OnlyClientCanCancel);
+        }
+
+        if escrow.state != EscrowState::Accepted {
+            return Err(Error::NotCancellable);
+        }
+
+        let vested = Self::vested_amount(&env, &escrow, &schedule) - (escrow.amount - escrow.locked_amount);
+        let refund = escrow.locked_amount - vested;
+
+        let (paid, platform_fee) = if vested > 0 {
+            Self::release_to_freelancer(&env, &mut escrow, vested)
+        } else {
+            (0, 0)
+        };
+        if refund > 0 {
+            let token_client = token::Client::new(&env, &escrow.token);
+            token_client.transfer(&env.current_contract_address(), &escrow.client, &refund);
+        }
+
+        escrow.locked_amount = 0;
+        Self::close_escrow(&env, escrow_id, &mut escrow, EscrowState::Cancelled);
+        Self::save_escrow(&env, escrow_id, &escrow);
+
+        env.events().publish(
+            (symbol_short!("stop"), client, escrow.freelancer.clone()),
+            StopEvent { header: Self::event_header(escrow_id, &escrow), refund, paid, platform_fee },
+        );
+
+        Ok(())
+    }
+
+    /// Propose a new deadline and/or new amounts for the unreleased
+    /// milestones. The other party has to accept it. A client proposing an
+    /// increase pays the difference in now; it is returned if the proposal is
+    /// withdrawn or replaced, or the escrow closes first. A new proposal
+    /// replaces any pending one.
+    pub fn propose_amendment(
+        env: Env,
+        proposer: Address,
+        escrow_id: u64,
+        deadline: Option<u64>,
+        amounts: Option<Vec<i128>>,
+    ) -> Result<(), Error> {
+        proposer.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        let escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        if proposer != escrow.client && proposer != escrow.freelancer {
+            return Err(Error::NotParty);
+        }
+
+        if !Self::is_open(&escrow) || escrow.kind != EscrowKind::LumpSum {
+            return Err(Error::InvalidAmendment);
+        }
+
+        if deadline.is_none() && amounts.is_none() {
+            return Err(Error::InvalidAmendment);
+        }
+
+        let deadline = deadline.unwrap_or(escrow.deadline);
+        if deadline <= env.ledger().timestamp() {
+            return Err(Error::DeadlineTooShort);
+        }
+
+        let from_milestone = Self::current_milestone(&escrow).ok_or(Error::NoMilestoneLeft)?;
+        let amounts = match amounts {
+            Some(amounts) => {
+                let mut milestones = Vec::new(&env);
+                let locked = Self::push_milestones(&mut milestones, &amounts)?;
+                let total = (escrow.amount - escrow.locked_amount)
+                    .checked_add(locked)
+                    .ok_or(Error::AmountOverflow)?;
+                Self::check_token_limits(&env, &escrow.token, total)?;
+                amounts
+            }
+            None => {
+                let mut amounts = Vec::new(&env);
+                for milestone in escrow.milestones.slice(from_milestone..).iter() {
+                    amounts.push_back(milestone.amount);
+                }
+                amounts
+            }
+        };
+
+        let increase = amounts.iter().sum::<i128>() - escrow.locked_amount;
+        let top_up = if proposer == escrow.client && increase > 0 { increase } else { 0 };
+
+        // Return the top-up held for the proposal being replaced
+        Self::drop_amendment(&env, escrow_id, &escrow.token);
+
+        if top_up > 0 {
+            let token_client = token::Client::new(&env, &escrow.token);
+            token_client.transfer(&escrow.client, &env.current_contract_address(), &top_up);
+        }
+
+        let amendment = Amendment {
+            proposer: proposer.clone(),
+            deadline,
+            amounts,
+            from_milestone,
+            top_up,
+        };
+        let key = DataKey::Amendment(escrow_id);
+        env.storage().persistent().set(&key, &amendment);
+        let extend_to = Self::escrow_ttl(&env, Self::live_until(&escrow).max(deadline));
+        env.storage().persistent().extend_ttl(&key, extend_to - DAY_IN_LEDGERS, extend_to);
+
+        env.events().publish(
+            (symbol_short!("proposal"), proposer.clone()),
+            ProposalEvent { header: Self::event_header(escrow_id, &escrow), proposer, deadline, increase, top_up },
+        );
+
+        Ok(())
+    }
+
+    /// Accept the other party's pending amendment. Increases are paid in by
+    /// the client, decreases refunded to the client, and the escrow's
+    /// `version` goes up by one.
+    pub fn accept_amendment(env: Env, acceptor: Address, escrow_id: u64) -> Result<(), Error> {
+        acceptor.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        let amendment = Self::get_amendment(env.clone(), escrow_id).ok_or(Error::NoPendingAmendment)?;
+
+        if acceptor != escrow.client && acceptor != escrow.freelancer {
+            return Err(Error::NotParty);
+        }
+
+        // Each side agrees: the proposer by proposing, the other by accepting
+        if acceptor == amendment.proposer {
+            return Err(Error::InvalidAmendment);
+        }
+
+        // Stale if a milestone was released or the escrow moved on since
+        if !Self::is_open(&escrow)
+            || Self::current_milestone(&escrow) != Some(amendment.from_milestone)
+            || amendment.deadline <= env.ledger().timestamp()
+        {
+            return Err(Error::InvalidAmendment);
+        }
+
+        let mut milestones = escrow.milestones.slice(..amendment.from_milestone);
+        let locked = Self::push_milestones(&mut milestones, &amendment.amounts)?;
+        let change = locked - escrow.locked_amount;
+
+        let token_client = token::Client::new(&env, &escrow.token);
+        let contract = env.current_contract_address();
+        if change > amendment.top_up {
+            token_client.transfer(&escrow.client, &contract, &(change - amendment.top_up));
+        } else if change < amendment.top_up {
+            token_client.transfer(&contract, &escrow.client, &(amendment.top_up - change));
+        }
+
+        escrow.amount += change;
+        escrow.locked_amount = locked;
+        escrow.milestones = milestones;
+        Self::unindex_expiry(&env, escrow_id, &escrow);
+        escrow.deadline = amendment.deadline;
+        Self::index_expiry(&env, escrow_id, &escrow);
+        escrow.version += 1;
+        env.storage().persistent().remove(&DataKey::Amendment(escrow_id));
+        Self::save_escrow(&env, escrow_id, &escrow);
+        Self::extend_user(&env, &escrow.client, escrow.deadline);
+        Self::extend_user(&env, &escrow.freelancer, escrow.deadline);
+
+        env.events().publish(
+            (symbol_short!("amend"), escrow.client.clone(), escrow.freelancer.clone()),
+            AmendEvent {
+                header: Self::event_header(escrow_id, &escrow),
+                acceptor,
+                version: escrow.version,
+                amount: escrow.amount,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Withdraw your own pending amendment, returning any top-up paid in.
+    pub fn withdraw_amendment(env: Env, proposer: Address, escrow_id: u64) -> Result<(), Error> {
+        proposer.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_refunds_allowed(&env)?;
+        let escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        let amendment = Self::get_amendment(env.clone(), escrow_id).ok_or(Error::NoPendingAmendment)?;
+
+        if proposer != amendment.proposer {
+            return Err(Error::NotParty);
+        }
+
+        let refund = Self::drop_amendment(&env, escrow_id, &escrow.token);
+
+        env.events().publish(
+            (symbol_short!("prop_wd"), proposer.clone()),
+            ProposalWithdrawnEvent { header: Self::event_header(escrow_id, &escrow), proposer, refund },
+        );
+
+        Ok(())
+    }
+
+    pub fn get_amendment(env: Env, escrow_id: u64) -> Option<Amendment> {
+        env.storage().persistent().get(&DataKey::Amendment(escrow_id))
+    }
+
+    pub fn dispute(env: Env, caller: Address, escrow_id: u64) -> Result<(), Error> {
+        caller.require_auth();
+        Self::extend_instance(&env);
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        if caller != escrow.client && caller != escrow.freelancer {
+             return Err(Error::OnlyPartiesCanDispute);
+        }
+
+        if !Self::is_open(&escrow) {
+             return Err(Error::NotDisputable);
+        }
+
+        if env.ledger().timestamp() > escrow.deadline {
+             return Err(Error::DeadlinePassed);
+        }
+
+        // Freeze the deadline and open the arbitration window
+        Self::unindex_expiry(&env, escrow_id, &escrow);
+        escrow.state = EscrowState::Disputed;
+        escrow.arbitration_deadline = env.ledger().timestamp() + ARBITRATION_WINDOW;
+        Self::index_expiry(&env, escrow_id, &escrow);
+        Self::save_escrow(&env, escrow_id, &escrow);
+        for member in escrow.panel.iter() {
+            Self::add_to_arbiter_queue(&env, &member, escrow_id, escrow.arbitration_deadline);
+        }
+
+        env.events().publish(
+            (symbol_short!("dispute"), caller.clone()),
+            DisputeEvent {
+                header: Self::event_header(escrow_id, &escrow),
+                caller,
+                arbitration_deadline: escrow.arbitration_deadline,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Record a content hash as evidence for the arbiter. Only the parties
+    /// can submit, only while disputed, and at most `MAX_EVIDENCE_PER_PARTY`
+    /// entries each. Entries are append-only.
+    pub fn submit_evidence(env: Env, submitter: Address, escrow_id: u64, hash: Bytes) -> Result<(), Error> {
+        submitter.require_auth();
+        Self::extend_instance(&env);
+        let escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        if submitter != escrow.client && submitter != escrow.freelancer {
+             return Err(Error::NotParty);
+        }
+
+        if escrow.state != EscrowState::Disputed {
+             return Err(Error::NotDisputed);
+        }
+
+        if hash.is_empty() || hash.len() > MAX_EVIDENCE_HASH_LEN {
+             return Err(Error::InvalidEvidence);
+        }
+
+        let mut evidence = Self::get_evidence(env.clone(), escrow_id);
+        let submitted = evidence.iter().filter(|e| e.submitter == submitter).count() as u32;
+        if submitted >= MAX_EVIDENCE_PER_PARTY {
+             return Err(Error::EvidenceLimitReached);
+        }
+
+        evidence.push_back(Evidence {
+            submitter: submitter.clone(),
+            hash: hash.clone(),
+            timestamp: env.ledger().timestamp(),
+        });
+
+        let key = DataKey::Evidence(escrow_id);
+        env.storage().persistent().set(&key, &evidence);
+        let extend_to = Self::escrow_ttl(&env, Self::live_until(&escrow));
+        env.storage().persistent().extend_ttl(&key, extend_to - DAY_IN_LEDGERS, extend_to);
+
+        env.events().publish(
+            (symbol_short!("evidence"), submitter.clone()),
+            EvidenceEvent { header: Self::event_header(escrow_id, &escrow), submitter, hash },
+        );
+
+        Ok(())
+    }
+
+    /// Evidence submitted for a dispute, in submission order.
+    pub fn get_evidence(env: Env, escrow_id: u64) -> Vec<Evidence> {
+        env.storage().persistent().get(&DataKey::Evidence(escrow_id)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Offer the other party a split of a disputed escrow's locked amount.
+    /// A new offer from either side replaces the pending one.
+    pub fn offer_settlement(env: Env, proposer: Address, escrow_id: u64, split: Split) -> Result<(), Error> {
+        proposer.require_auth();
+        Self::extend_instance(&env);
+        let escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        if proposer != escrow.client && proposer != escrow.freelancer {
+            return Err(Error::NotParty);
+        }
+
+        if escrow.state != EscrowState::Disputed {
+            return Err(Error::NotDisputed);
+        }
+
+        Self::split_amounts(escrow.locked_amount, &split)?;
+
+        let offer = SettlementOffer {
+            proposer: proposer.clone(),
+            split: split.clone(),
+            locked_amount: escrow.locked_amount,
+        };
+        let key = DataKey::Settlement(escrow_id);
+        env.storage().persistent().set(&key, &offer);
+        let extend_to = Self::escrow_ttl(&env, Self::live_until(&escrow));
+        env.storage().persistent().extend_ttl(&key, extend_to - DAY_IN_LEDGERS, extend_to);
+
+        env.events().publish(
+            (symbol_short!("offer"), proposer.clone()),
+            OfferEvent { header: Self::event_header(escrow_id, &escrow), proposer, split },
+        );
+
+        Ok(())
+    }
+
+    /// Accept the other party's settlement offer. The escrow is paid out
+    /// straight away, without an arbiter fee, and closes as `Settled`.
+    pub fn accept_settlement(env: Env, acceptor: Address, escrow_id: u64) -> Result<(), Error> {
+        acceptor.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+        let offer = Self::get_settlement_offer(env.clone(), escrow_id).ok_or(Error::NoSettlementOffer)?;
+
+        if acceptor != escrow.client && acceptor != escrow.freelancer {
+            return Err(Error::NotParty);
+        }
+
+        if escrow.state != EscrowState::Disputed {
+            return Err(Error::NotDisputed);
+        }
+
+        // Each side agrees: the proposer by offering, the other by accepting.
+        // A milestone released since the offer makes it stale.
+        if acceptor == offer.proposer || offer.locked_amount != escrow.locked_amount {
+            return Err(Error::InvalidSettlement);
+        }
+
+        env.storage().persistent().remove(&DataKey::Settlement(escrow_id));
+        let (client_amount, freelancer_paid, _, platform_fee) = Self::settle_dispute(
+            &env, escrow_id, &mut escrow, &offer.split, &Vec::new(&env), EscrowState::Settled,
+        )?;
+
+        env.events().publish(
+            (symbol_short!("settle"), escrow.client.clone(), escrow.freelancer.clone()),
+            PayoutEvent {
+                header: Self::event_header(escrow_id, &escrow),
+                arbiter: None,
+                client_amount,
+                freelancer_paid,
+                arbiter_fee: 0,
+                platform_fee,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn get_settlement_offer(env: Env, escrow_id: u64) -> Option<SettlementOffer> {
+        env.storage().persistent().get(&DataKey::Settlement(escrow_id))
+    }
+
+    /// Award every unreleased milestone to the winner. Milestones already
+    /// paid out are left untouched.
+    pub fn resolve(env: Env, arbiter: Address, escrow_id: u64, winner: Address) -> Result<(), Error> {
+        arbiter.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        Self::check_sole_arbiter(&escrow, &arbiter)?;
+
+        if escrow.state != EscrowState::Disputed && !Self::is_open(&escrow) {
+             return Err(Error::NotResolvable);
+        }
+
+        if winner != escrow.client && winner != escrow.freelancer {
+             return Err(Error::InvalidWinner);
+        }
+
+        // Transfer locked amount, less the arbiter fee, to winner
+        let split = if winner == escrow.freelancer { Split::Bps(10_000) } else { Split::Bps(0) };
+        let panel = escrow.panel.clone();
+        let (client_amount, freelancer_paid, arbiter_fee, platform_fee) =
+            Self::settle_dispute(&env, escrow_id, &mut escrow, &split, &panel, EscrowState::Resolved)?;
+
+        env.events().publish(
+            (symbol_short!("resolve"), arbiter.clone(), winner),
+            PayoutEvent {
+                header: Self::event_header(escrow_id, &escrow),
+                arbiter: Some(arbiter),
+                client_amount,
+                freelancer_paid,
+                arbiter_fee,
+                platform_fee,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Settle a dispute as a compromise: the locked amount is divided between
+    /// client and freelancer according to `split`.
+    pub fn resolve_split(env: Env, arbiter: Address, escrow_id: u64, split: Split) -> Result<(), Error> {
+        arbiter.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        Self::check_sole_arbiter(&escrow, &arbiter)?;
+
+        if escrow.state != EscrowState::Disputed && !Self::is_open(&escrow) {
+             return Err(Error::NotResolvable);
+        }
+
+        let panel = escrow.panel.clone();
+        let (client_amount, freelancer_paid, arbiter_fee, platform_fee) =
+            Self::settle_dispute(&env, escrow_id, &mut escrow, &split, &panel, EscrowState::Resolved)?;
+
+        env.events().publish(
+            (symbol_short!("resolve"), arbiter.clone()),
+            PayoutEvent {
+                header: Self::event_header(escrow_id, &escrow),
+                arbiter: Some(arbiter),
+                client_amount,
+                freelancer_paid,
+                arbiter_fee,
+                platform_fee,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Cast a panel member's vote for an outcome (`Split::Bps(0)` refunds the
+    /// client, `Split::Bps(10_000)` releases to the freelancer). The escrow
+    /// settles as soon as `threshold` members agree on the same outcome; the
+    /// arbiter fee is shared by those members. Voting again replaces the
+    /// member's earlier vote. Returns true once settled.
+    pub fn vote(env: Env, arbiter: Address, escrow_id: u64, outcome: Split) -> Result<bool, Error> {
+        arbiter.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_not_paused(&env)?;
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        if !escrow.panel.contains(&arbiter) {
+             return Err(Error::NotArbiter);
+        }
+
+        if escrow.state != EscrowState::Disputed {
+             return Err(Error::NotResolvable);
+        }
+
+        // Reject outcomes that could never be paid out. Settlement shares the
+        // arbiter fee between exactly `threshold` members.
+        let fee_share = Self::bps_of(escrow.locked_amount, escrow.arbiter_fee_bps) / escrow.threshold as i128;
+        Self::split_amounts(escrow.locked_amount - fee_share * escrow.threshold as i128, &outcome)?;
+
+        let mut votes = Self::get_votes(env.clone(), escrow_id);
+        if let Some(index) = votes.iter().position(|v| v.arbiter == arbiter) {
+            votes.remove(index as u32);
+        }
+        votes.push_back(Vote {
+            arbiter: arbiter.clone(),
+            outcome: outcome.clone(),
+            timestamp: env.ledger().timestamp(),
+        });
+        Self::save_votes(&env, escrow_id, &votes, Self::live_until(&escrow));
+
+        env.events().publish(
+            (symbol_short!("vote"), arbiter.clone()),
+            VoteEvent { header: Self::event_header(escrow_id, &escrow), arbiter: arbiter.clone(), outcome: outcome.clone() },
+        );
+
+        let mut majority = Vec::new(&env);
+        for v in votes.iter() {
+            if v.outcome == outcome {
+                majority.push_back(v.arbiter);
+            }
+        }
+        if majority.len() < escrow.threshold {
+            return Ok(false);
+        }
+
+        let (client_amount, freelancer_paid, arbiter_fee, platform_fee) =
+            Self::settle_dispute(&env, escrow_id, &mut escrow, &outcome, &majority, EscrowState::Resolved)?;
+
+        env.events().publish(
+            (symbol_short!("resolve"), arbiter.clone()),
+            PayoutEvent {
+                header: Self::event_header(escrow_id, &escrow),
+                arbiter: Some(arbiter),
+                client_amount,
+                freelancer_paid,
+                arbiter_fee,
+                platform_fee,
+            },
+        );
+
+        Ok(true)
+    }
+
+    pub fn get_votes(env: Env, escrow_id: u64) -> Vec<Vote> {
+        env.storage().persistent().get(&DataKey::Votes(escrow_id)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Claim timeout: if the deadline has passed and escrow is still open,
+    /// anyone can trigger a refund of the unreleased milestones to the
+    /// client. A disputed escrow instead waits for the arbitration window to
+    /// close, then settles according to its fallback outcome. The caller
+    /// (`keeper`) receives the escrow's keeper bounty, if any, unless the
+    /// contract is paused.
+    pub fn claim_timeout(env: Env, keeper: Address, escrow_id: u64) -> Result<(), Error> {
+        keeper.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_refunds_allowed(&env)?;
+        Self::claim_expired(&env, &keeper, escrow_id)
+    }
+
+    /// `claim_timeout` for every escrow in `escrow_ids` in one call.
+    /// All-or-nothing: if any claim fails, none is applied.
+    pub fn claim_timeout_batch(env: Env, keeper: Address, escrow_ids: Vec<u64>) -> Result<(), Error> {
+        keeper.require_auth();
+        Self::extend_instance(&env);
+        Self::ensure_refunds_allowed(&env)?;
+        Self::check_batch_size(escrow_ids.len())?;
+
+        for escrow_id in escrow_ids.iter() {
+            Self::claim_expired(&env, &keeper, escrow_id)?;
+        }
+        Ok(())
+    }
+
+    /// One-time setup: stores the admin and the current storage version.
+    /// Must be authorized by the deployer (`DEPLOYER`) and the admin.
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+        let deployer = Address::from_string(&String::from_str(&env, DEPLOYER));
+        deployer.require_auth();
+        if admin != deployer {
+            admin.require_auth();
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&DataKey::StorageVersion, &STORAGE_VERSION);
+        Self::extend_instance(&env);
+
+        env.events().publish((symbol_short!("init"), admin), STORAGE_VERSION);
+
+        Ok(())
+    }
+
+    /// First step of an admin handover. The new admin must call
+    /// `accept_admin` before it takes effect.
+    pub fn transfer_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        let admin = Self::require_admin(&env)?;
+        Self::extend_instance(&env);
+
+        env.storage().instance().set(&DataKey::PendingAdmin, &new_admin);
+
+        env.events().publish((symbol_short!("adm_prop"), admin), new_admin);
+
+        Ok(())
+    }
+
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        let new_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .ok_or(Error::NoPendingAdmin)?;
+        new_admin.require_auth();
+        Self::extend_instance(&env);
+
+        let old_admin = Self::get_admin(env.clone())?;
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+
+        env.events().publish((symbol_short!("adm_acc"), old_admin), new_admin);
+
+        Ok(())
+    }
+
+    pub fn get_admin(env: Env) -> Result<Address, Error> {
+        env.storage().instance().get(&DataKey::Admin).ok_or(Error::NotInitialized)
+    }
+
+    /// Replace the contract WASM. Stored escrows are kept; run `migrate`
+    /// afterwards if the new code bumps `STORAGE_VERSION`.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        let admin = Self::require_admin(&env)?;
+        Self::extend_instance(&env);
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+
+        env.events().publish((symbol_short!("upgrade"), admin), new_wasm_hash);
+
+        Ok(())
+    }
+
+    /// Bring stored data up to `STORAGE_VERSION`, one version at a time.
+    /// Escrows can't be read until this has run after an upgrade.
+    pub fn migrate(env: Env) -> Result<u32, Error> {
+        let admin = Self::require_admin(&env)?;
+        Self::extend_instance(&env);
+
+        let from = Self::get_storage_version(env.clone());
+        // No layout changes yet: every older version maps directly onto the current one
+        env.storage().instance().set(&DataKey::StorageVersion, &STORAGE_VERSION);
+
+        if from != STORAGE_VERSION {
+            env.events().publish((symbol_short!("migrate"), admin), (from, STORAGE_VERSION));
+        }
+
+        Ok(STORAGE_VERSION)
+    }
+
+    pub fn get_storage_version(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::StorageVersion).unwrap_or(0)
+    }
+
+    /// Circuit breaker: blocks new deposits and every outgoing transfer.
+    /// Client refunds through `cancel`/`claim_timeout` reopen once
+    /// `PAUSE_GRACE_PERIOD` has passed.
+    pub fn pause(env: Env) -> Result<(), Error> {
+        let admin = Self::require_admin(&env)?;
+        Self::extend_instance(&env);
+
+        if !Self::is_paused(env.clone()) {
+            env.storage().instance().set(&DataKey::PausedAt, &env.ledger().timestamp());
+            env.events().publish((symbol_short!("pause"), admin), env.ledger().timestamp());
+        }
+
+        Ok(())
+    }
+
+    pub fn unpause(env: Env) -> Result<(), Error> {
+        let admin = Self::require_admin(&env)?;
+        Self::extend_instance(&env);
+
+        if Self::is_paused(env.clone()) {
+            env.storage().instance().remove(&DataKey::PausedAt);
+            env.events().publish((symbol_short!("unpause"), admin), env.ledger().timestamp());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env) -> bool {
+        env.storage().instance().has(&DataKey::PausedAt)
+    }
+
+    /// Add a token to the registry of accepted escrow assets, or update its
+    /// deposit bounds. Admin only.
+    pub fn allow_token(
+        env: Env,
+        token: Address,
+        min_amount: Option<i128>,
+        max_amount: Option<i128>,
+    ) -> Result<(), Error> {
+        let admin = Self::require_admin(&env)?;
+        Self::extend_instance(&env);
+
+        if min_amount.is_some_and(|min| min <= 0) {
+            return Err(Error::InvalidTokenLimits);
+        }
+        if let (Some(min), Some(max)) = (min_amount, max_amount) {
+            if max < min {
+                return Err(Error::InvalidTokenLimits);
+            }
+        }
+
+        let limits = TokenLimits { min_amount, max_amount };
+        let mut tokens = Self::allowed_tokens(&env);
+        tokens.set(token.clone(), limits.clone());
+        env.storage().instance().set(&DataKey::AllowedTokens, &tokens);
+
+        env.events().publish((symbol_short!("tok_add"), admin, token), limits);
+
+        Ok(())
+    }
+
+    /// Stop accepting a token for new deposits. Existing escrows are unaffected.
+    pub fn remove_token(env: Env, token: Address) -> Result<(), Error> {
+        let admin = Self::require_admin(&env)?;
+        Self::extend_instance(&env);
+
+        let mut tokens = Self::allowed_tokens(&env);
+        if tokens.remove(token.clone()).is_none() {
+            return Err(Error::TokenNotAllowed);
+        }
+        env.storage().instance().set(&DataKey::AllowedTokens, &tokens);
+
+        env.events().publish((symbol_short!("tok_rm"), admin, token), ());
+
+        Ok(())
+    }
+
+    pub fn get_allowed_tokens(env: Env) -> Vec<AllowedToken> {
+        let mut list = Vec::new(&env);
+        for (token, limits) in Self::allowed_tokens(&env).iter() {
+            list.push_back(AllowedToken { token, limits });
+        }
+        list
+    }
+
+    pub fn get_token_limits(env: Env, token: Address) -> Option<TokenLimits> {
+        Self::allowed_tokens(&env).get(token)
+    }
+
+    /// Set the platform treasury and fee rates. Admin only.
+    pub fn set_fee_config(env: Env, config: FeeConfig) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        Self::extend_instance(&env);
+
+        if config.platform_fee_bps > MAX_FEE_BPS || config.arbiter_fee_bps > MAX_FEE_BPS {
+            return Err(Error::InvalidFee);
+        }
+
+        env.storage().instance().set(&DataKey::FeeConfig, &config);
+
+        env.events().publish(
+            (symbol_short!("fee_cfg"), config.treasury),
+            (config.platform_fee_bps, config.arbiter_fee_bps),
+        );
+
+        Ok(())
+    }
+
+    pub fn get_fee_config(env: Env) -> Option<FeeConfig> {
+        env.storage().instance().get(&DataKey::FeeConfig)
+    }
+
+    /// Extend the TTL of an escrow and of its parties' indexes. Anyone can
+    /// call this to keep a long-running escrow from being archived.
+    pub fn extend_escrow_ttl(env: Env, escrow_id: u64) -> Result<(), Error> {
+        Self::extend_instance(&env);
+        let escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        let live_until = Self::live_until(&escrow);
+        Self::extend_escrow(&env, escrow_id, live_until);
+        Self::extend_user(&env, &escrow.client, live_until);
+        Self::extend_user(&env, &escrow.freelancer, live_until);
+
+        Ok(())
+    }
+
+    pub fn get_escrow(env: Env, escrow_id: u64) -> Result<Escrow, Error> {
+        Self::ensure_migrated(&env)?;
+        env.storage().persistent().get(&DataKey::Escrow(escrow_id)).ok_or(Error::EscrowNotFound)
+    }
+
+    pub fn get_user_escrows(env: Env, user: Address) -> Vec<u64> {
+        env.storage().persistent().get(&DataKey::UserEscrows(user)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Disputed escrows waiting on this arbiter, oldest dispute first.
+    pub fn get_arbiter_queue(env: Env, arbiter: Address) -> Vec<EscrowEntry> {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ArbiterQueue(arbiter))
+            .unwrap_or(Vec::new(&env));
 
-#[contracttype]
-pub enum DataKey {
-    NextEscrowId,
-    Escrow(u64),
-    UserEscrows(Address),
-}
+        let mut queue = Vec::new(&env);
+        for id in ids.iter() {
+            if let Some(escrow) = env.storage().persistent().get::<_, Escrow>(&DataKey::Escrow(id)) {
+                queue.push_back(EscrowEntry { id, escrow });
+            }
+        }
+        queue
+    }
 
-#[contract]
-pub struct SafeHandsContract;
+    /// Escrows whose timeout becomes claimable before `before_ts`: open ones
+    /// past their deadline, disputed ones past their arbitration window.
+    /// Ordered by deadline day, at most `limit` entries (capped at
+    /// `MAX_PAGE_SIZE`).
+    pub fn get_expirable(env: Env, before_ts: u64, limit: u32) -> Vec<EscrowEntry> {
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let days: Vec<u64> = env.storage().persistent().get(&DataKey::ExpiryDays).unwrap_or(Vec::new(&env));
 
-#[contractimpl]
-impl SafeHandsContract {
+        let mut expirable = Vec::new(&env);
+        let mut scanned = 0;
+        for day in days.iter() {
+            if day * 86400 >= before_ts {
+                break;
+            }
+            let bucket: Vec<u64> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::ExpiryBucket(day))
+                .unwrap_or(Vec::new(&env));
+            for id in bucket.iter() {
+                if expirable.len() >= limit || scanned >= MAX_PAGE_SCAN {
+                    return expirable;
+                }
+                scanned += 1;
+                if let Some(escrow) = env.storage().persistent().get::<_, Escrow>(&DataKey::Escrow(id)) {
+                    if Self::claimable_after(&escrow) < before_ts {
+                        expirable.push_back(EscrowEntry { id, escrow });
+                    }
+                }
+            }
+        }
+        expirable
+    }
 
-    pub fn deposit(
+    /// Page through a user's escrows, returning full records. `state` and
+    /// `role` filters match everything when `None`. `cursor` is a position
+    /// in the user's index (start at 0); filtered-out entries still count
+    /// towards the scan budget, so a page may come back short with a
+    /// `next_cursor` to continue from.
+    pub fn get_user_escrows_page(
+        env: Env,
+        user: Address,
+        state: Option<EscrowState>,
+        role: Option<Role>,
+        cursor: u32,
+        limit: u32,
+    ) -> EscrowPage {
+        let ids = Self::get_user_escrows(env.clone(), user.clone());
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        let scan_end = ids.len().min(cursor.saturating_add(MAX_PAGE_SCAN));
+
+        let mut escrows = Vec::new(&env);
+        let mut position = cursor;
+        while position < scan_end && escrows.len() < limit {
+            let id = ids.get_unchecked(position);
+            position += 1;
+
+            let Some(escrow) = env.storage().persistent().get::<_, Escrow>(&DataKey::Escrow(id)) else {
+                continue;
+            };
+            if Self::matches_filter(&escrow, &user, state, role) {
+                escrows.push_back(EscrowEntry { id, escrow });
+            }
+        }
+
+        let next_cursor = if position < ids.len() { Some(position) } else { None };
+        EscrowPage { escrows, next_cursor }
+    }
+
+    // Helpers
+
+    // Escrows stored under an older layout may not decode until `migrate`
+    // has brought them up to date
+    fn ensure_migrated(env: &Env) -> Result<(), Error> {
+        match env.storage().instance().get::<_, u32>(&DataKey::StorageVersion) {
+            Some(version) if version < STORAGE_VERSION => Err(Error::MigrationPending),
+            _ => Ok(()),
+        }
+    }
+
+    fn create_escrow(
         env: Env,
         client: Address,
         freelancer: Address,
-        arbiter: Address,
         token: Address,
-        amount: i128,
+        amounts: Vec<i128>,
         deadline_days: u64,
-    ) -> u64 {
+        options: EscrowOptions,
+    ) -> Result<u64, Error> {
         client.require_auth();
+        Self::ensure_not_paused(&env)?;
 
-        if amount <= 0 {
-            panic!("Amount must be positive");
-        }
-
-        if deadline_days == 0 {
-            panic!("Deadline must be at least 1 day");
-        }
+        let (escrow_id, amount) =
+            Self::open_escrow(env.clone(), client.clone(), freelancer, token.clone(), amounts, deadline_days, options)?;
 
         // Transfer funds to contract
         let token_client = token::Client::new(&env, &token);
@@ -65,221 +1691,727 @@ impl SafeHandsContract {
             &amount,
         );
 
+        Ok(escrow_id)
+    }
+
+    // Validates and stores a new escrow, indexes it and emits the deposit
+    // event. The caller authorizes the client and collects the funds.
+    // Returns (escrow ID, amount to collect including the keeper bounty).
+    fn open_escrow(
+        env: Env,
+        client: Address,
+        freelancer: Address,
+        token: Address,
+        amounts: Vec<i128>,
+        deadline_days: u64,
+        options: EscrowOptions,
+    ) -> Result<(u64, i128), Error> {
+        let EscrowOptions { terms_hash, terms_uri, panel, threshold, fallback, kind, keeper_bounty } = options;
+        Self::check_panel(&panel, threshold, &client, &freelancer)?;
+        let arbiter = panel.get_unchecked(0);
+
+        if terms_uri.as_ref().is_some_and(|uri| uri.is_empty() || uri.len() > MAX_TERMS_URI_LEN) {
+            return Err(Error::InvalidTermsUri);
+        }
+
+        if let Fallback::Split(bps) = fallback {
+            if bps as i128 > BPS_DENOMINATOR {
+                return Err(Error::InvalidSplit);
+            }
+        }
+
+        if keeper_bounty < 0 {
+            return Err(Error::AmountMustBePositive);
+        }
+
+        let mut milestones = Vec::new(&env);
+        let amount = Self::push_milestones(&mut milestones, &amounts)?;
+
+        if deadline_days == 0 {
+            return Err(Error::DeadlineTooShort);
+        }
+
+        Self::check_token_limits(&env, &token, amount)?;
+
         // Generate ID
         let escrow_id = Self::get_next_escrow_id(&env);
 
         // Calculate deadline timestamp (current ledger time + days in seconds)
         let deadline = env.ledger().timestamp() + (deadline_days * 86400);
 
+        // A stream is paid out as one milestone and has to finish vesting
+        // before the deadline
+        if let EscrowKind::Stream(schedule) = &kind {
+            if amounts.len() != 1
+                || schedule.start > schedule.cliff
+                || schedule.cliff > schedule.end
+                || schedule.start >= schedule.end
+                || schedule.end > deadline
+            {
+                return Err(Error::InvalidVesting);
+            }
+        }
+
+        // Lock in the current fees and treasury for this job
+        let (platform_fee_bps, arbiter_fee_bps, treasury) = match Self::get_fee_config(env.clone()) {
+            Some(config) => (config.platform_fee_bps, config.arbiter_fee_bps, Some(config.treasury)),
+            None => (0, 0, None),
+        };
+
         let escrow = Escrow {
             client: client.clone(),
             freelancer: freelancer.clone(),
-            arbiter: arbiter.clone(),
+            arbiter,
+            panel: panel.clone(),
+            threshold,
             amount,
             token,
-            approved_by_client: false,
-            approved_by_freelancer: false,
+            milestones,
+            released_amount: 0,
+            locked_amount: amount,
+            fees_paid: 0,
+            platform_fee_bps,
+            arbiter_fee_bps,
+            treasury,
             state: EscrowState::Funded,
             deadline,
+            fallback,
+            arbitration_deadline: 0,
+            version: 0,
+            terms_hash,
+            terms_uri,
+            accepted_at: None,
+            kind,
+            keeper_bounty,
         };
 
         // Store Escrow
-        env.storage().persistent().set(&DataKey::Escrow(escrow_id), &escrow);
+        Self::save_escrow(&env, escrow_id, &escrow);
+        Self::index_expiry(&env, escrow_id, &escrow);
         
         // Update Indexes
-        Self::add_escrow_to_user(&env, client.clone(), escrow_id);
-        Self::add_escrow_to_user(&env, freelancer.clone(), escrow_id);
+        Self::add_escrow_to_user(&env, client.clone(), escrow_id, deadline);
+        if freelancer != client {
+            Self::add_escrow_to_user(&env, freelancer.clone(), escrow_id, deadline);
+        }
+        for member in panel.iter() {
+            if member != client && member != freelancer {
+                Self::add_escrow_to_user(&env, member, escrow_id, deadline);
+            }
+        }
         
         // Increment ID
-        let next_id = escrow_id.checked_add(1).expect("Escrow ID limit reached");
+        let next_id = escrow_id.checked_add(1).ok_or(Error::EscrowIdLimitReached)?;
         env.storage().instance().set(&DataKey::NextEscrowId, &next_id);
+        Self::extend_instance(&env);
 
         // Emit Event
         env.events().publish(
-            (symbol_short!("deposit"), client, freelancer),
-            (escrow_id, amount),
+            (symbol_short!("deposit"), client.clone(), freelancer.clone()),
+            DepositEvent { header: Self::event_header(escrow_id, &escrow), client, freelancer, amount, keeper_bounty },
         );
 
-        escrow_id
+        let total = amount.checked_add(keeper_bounty).ok_or(Error::AmountOverflow)?;
+        Ok((escrow_id, total))
     }
 
-    pub fn approve(env: Env, approver: Address, escrow_id: u64) {
-        approver.require_auth();
+    fn matches_filter(escrow: &Escrow, user: &Address, state: Option<EscrowState>, role: Option<Role>) -> bool {
+        if state.is_some_and(|state| state != escrow.state) {
+            return false;
+        }
+        match role {
+            Some(Role::Client) => escrow.client == *user,
+            Some(Role::Freelancer) => escrow.freelancer == *user,
+            Some(Role::Arbiter) => escrow.panel.contains(user),
+            None => true,
+        }
+    }
 
-        let mut escrow = Self::get_escrow(env.clone(), escrow_id);
+    fn allowed_tokens(env: &Env) -> Map<Address, TokenLimits> {
+        env.storage().instance().get(&DataKey::AllowedTokens).unwrap_or(Map::new(env))
+    }
 
-        if escrow.state != EscrowState::Funded && escrow.state != EscrowState::Disputed {
-            panic!("Escrow not in a state to be approved");
+    fn ensure_not_paused(env: &Env) -> Result<(), Error> {
+        if Self::is_paused(env.clone()) {
+            return Err(Error::ContractPaused);
         }
+        Ok(())
+    }
 
-        // Block approval if deadline has passed
-        if env.ledger().timestamp() > escrow.deadline {
-            panic!("Escrow deadline has passed. Use claim_timeout instead.");
+    fn ensure_refunds_allowed(env: &Env) -> Result<(), Error> {
+        let paused_at: Option<u64> = env.storage().instance().get(&DataKey::PausedAt);
+        match paused_at {
+            Some(paused_at) if env.ledger().timestamp() < paused_at + PAUSE_GRACE_PERIOD => {
+                Err(Error::ContractPaused)
+            }
+            _ => Ok(()),
         }
+    }
 
-        if approver == escrow.client {
-            escrow.approved_by_client = true;
-        } else if approver == escrow.freelancer {
-            escrow.approved_by_freelancer = true;
-        } else {
-            panic!("Not authorized to approve");
+    fn check_panel(panel: &Vec<Address>, threshold: u32, client: &Address, freelancer: &Address) -> Result<(), Error> {
+        if panel.is_empty() || panel.len() > MAX_PANEL_SIZE {
+            return Err(Error::InvalidPanel);
         }
-
-        // Check for Release condition (Both approved)
-        if escrow.approved_by_client && escrow.approved_by_freelancer {
-            let token_client = token::Client::new(&env, &escrow.token);
-            token_client.transfer(
-                &env.current_contract_address(),
-                &escrow.freelancer,
-                &escrow.amount,
-            );
-            escrow.state = EscrowState::Released;
-
-            env.events().publish(
-                (symbol_short!("release"), escrow.client.clone(), escrow.freelancer.clone()),
-                (escrow_id, escrow.amount),
-            );
+        if threshold == 0 || threshold > panel.len() {
+            return Err(Error::InvalidPanel);
         }
-
-        env.storage().persistent().set(&DataKey::Escrow(escrow_id), &escrow);
+        for (i, member) in panel.iter().enumerate() {
+            // A sole arbiter may also be a party, but a panel must be
+            // independent of both parties and free of duplicates
+            if panel.len() > 1 && (member == *client || member == *freelancer) {
+                return Err(Error::InvalidPanel);
+            }
+            if panel.first_index_of(&member) != Some(i as u32) {
+                return Err(Error::InvalidPanel);
+            }
+        }
+        Ok(())
     }
 
-    pub fn cancel(env: Env, caller: Address, escrow_id: u64) {
-        caller.require_auth();
-        let mut escrow = Self::get_escrow(env.clone(), escrow_id);
-        
-        if caller != escrow.client {
-             panic!("Only client can cancel");
+    // Refunds an open escrow past its deadline, or applies the fallback to
+    // a disputed one past its arbitration window
+    fn claim_expired(env: &Env, keeper: &Address, escrow_id: u64) -> Result<(), Error> {
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        match escrow.state {
+            EscrowState::Funded | EscrowState::Accepted => {}
+            EscrowState::Disputed => return Self::claim_fallback(env, keeper, escrow_id, &mut escrow),
+            _ => return Err(Error::NotClaimable),
         }
 
-        if escrow.state != EscrowState::Funded {
-             panic!("Escrow cannot be cancelled in current state");
+        // An accepted stream is fully vested by the deadline; it belongs to
+        // the freelancer
+        if escrow.state == EscrowState::Accepted && escrow.kind != EscrowKind::LumpSum {
+            return Err(Error::NotClaimable);
         }
 
-        if escrow.approved_by_freelancer {
-             panic!("Cannot cancel: Freelancer has already accepted/approved");
+        if env.ledger().timestamp() <= escrow.deadline {
+            return Err(Error::DeadlineNotPassed);
         }
 
-        // Refund
-        let token_client = token::Client::new(&env, &escrow.token);
+        // Refund to client
+        let refund = escrow.locked_amount;
+        let token_client = token::Client::new(env, &escrow.token);
         token_client.transfer(
             &env.current_contract_address(),
             &escrow.client,
-            &escrow.amount,
+            &refund,
         );
-        
-        escrow.state = EscrowState::Cancelled;
-        env.storage().persistent().set(&DataKey::Escrow(escrow_id), &escrow);
+
+        escrow.locked_amount = 0;
+        let bounty = Self::pay_bounty(env, keeper, &mut escrow);
+        Self::close_escrow(env, escrow_id, &mut escrow, EscrowState::Cancelled);
+        Self::save_escrow(env, escrow_id, &escrow);
 
         env.events().publish(
-            (symbol_short!("cancel"), escrow.client.clone()),
-            (escrow_id, escrow.amount),
+            (symbol_short!("timeout"), escrow.client.clone()),
+            TimeoutEvent { header: Self::event_header(escrow_id, &escrow), keeper: keeper.clone(), refund },
         );
-    }
+        Self::publish_bounty(env, keeper, escrow_id, &escrow, bounty);
 
-    pub fn dispute(env: Env, caller: Address, escrow_id: u64) {
-        caller.require_auth();
-        let mut escrow = Self::get_escrow(env.clone(), escrow_id);
+        Ok(())
+    }
 
-        if caller != escrow.client && caller != escrow.freelancer {
-             panic!("Only parties can raise dispute");
+    fn claim_fallback(env: &Env, keeper: &Address, escrow_id: u64, escrow: &mut Escrow) -> Result<(), Error> {
+        if env.ledger().timestamp() <= escrow.arbitration_deadline {
+            return Err(Error::ArbitrationWindowOpen);
         }
 
-        if escrow.state != EscrowState::Funded {
-             panic!("Can only dispute active/funded escrows");
+        let split = match escrow.fallback {
+            Fallback::Refund => Split::Bps(0),
+            Fallback::Release => Split::Bps(10_000),
+            Fallback::Split(bps) => Split::Bps(bps),
+        };
+        // Only refunds get through during the pause grace period
+        if split != Split::Bps(0) {
+            Self::ensure_not_paused(env)?;
         }
 
-        escrow.state = EscrowState::Disputed;
-        env.storage().persistent().set(&DataKey::Escrow(escrow_id), &escrow);
+        let bounty = Self::pay_bounty(env, keeper, escrow);
+
+        // No arbiter fee: the arbiter didn't decide
+        let (client_amount, freelancer_paid, _, platform_fee) =
+            Self::settle_dispute(env, escrow_id, escrow, &split, &Vec::new(env), EscrowState::Resolved)?;
 
         env.events().publish(
-            (symbol_short!("dispute"), caller),
-            escrow_id,
+            (symbol_short!("fallback"), escrow.client.clone(), escrow.freelancer.clone()),
+            PayoutEvent {
+                header: Self::event_header(escrow_id, escrow),
+                arbiter: None,
+                client_amount,
+                freelancer_paid,
+                arbiter_fee: 0,
+                platform_fee,
+            },
         );
+        Self::publish_bounty(env, keeper, escrow_id, escrow, bounty);
+
+        Ok(())
     }
 
-    pub fn resolve(env: Env, arbiter: Address, escrow_id: u64, winner: Address) {
-        arbiter.require_auth();
-        let mut escrow = Self::get_escrow(env.clone(), escrow_id);
+    // `resolve`/`resolve_split` are for escrows with a single arbiter;
+    // panels decide through `vote`
+    fn check_sole_arbiter(escrow: &Escrow, arbiter: &Address) -> Result<(), Error> {
+        if !escrow.panel.contains(arbiter) {
+            return Err(Error::NotArbiter);
+        }
+        if escrow.panel.len() > 1 {
+            return Err(Error::PanelVoteRequired);
+        }
+        Ok(())
+    }
+
+    // Pays out the locked amount and closes the escrow in `state`: the
+    // arbiter fee first, shared equally by `arbiters` (none if empty), then
+    // `split` of the rest. Returns (client amount, freelancer amount paid,
+    // arbiter fee, platform fee).
+    fn settle_dispute(
+        env: &Env,
+        escrow_id: u64,
+        escrow: &mut Escrow,
+        split: &Split,
+        arbiters: &Vec<Address>,
+        state: EscrowState,
+    ) -> Result<(i128, i128, i128, i128), Error> {
+        let fee_share = if arbiters.is_empty() {
+            0
+        } else {
+            Self::bps_of(escrow.locked_amount, escrow.arbiter_fee_bps) / arbiters.len() as i128
+        };
+        let arbiter_fee = fee_share * arbiters.len() as i128;
+        let (client_amount, freelancer_amount) = Self::split_amounts(escrow.locked_amount - arbiter_fee, split)?;
+
+        let token_client = token::Client::new(env, &escrow.token);
+        if fee_share > 0 {
+            for arbiter in arbiters.iter() {
+                token_client.transfer(&env.current_contract_address(), &arbiter, &fee_share);
+            }
+            escrow.fees_paid += arbiter_fee;
+        }
+        if client_amount > 0 {
+            token_client.transfer(&env.current_contract_address(), &escrow.client, &client_amount);
+        }
+        let (freelancer_paid, platform_fee) = if freelancer_amount > 0 {
+            Self::release_to_freelancer(env, escrow, freelancer_amount)
+        } else {
+            (0, 0)
+        };
+
+        escrow.locked_amount = 0;
+        Self::close_escrow(env, escrow_id, escrow, state);
+        Self::save_escrow(env, escrow_id, escrow);
+
+        Ok((client_amount, freelancer_paid, arbiter_fee, platform_fee))
+    }
+
+    fn require_admin(env: &Env) -> Result<Address, Error> {
+        let admin = Self::get_admin(env.clone())?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    fn get_next_escrow_id(env: &Env) -> u64 {
+        env.storage().instance().get(&DataKey::NextEscrowId).unwrap_or(0)
+    }
+
+    fn bps_of(amount: i128, bps: u32) -> i128 {
+        amount * (bps as i128) / BPS_DENOMINATOR
+    }
+
+    // Pays `gross` to the freelancer minus the platform fee, which goes to the
+    // escrow's treasury. Returns (paid to freelancer, platform fee).
+    fn release_to_freelancer(env: &Env, escrow: &mut Escrow, gross: i128) -> (i128, i128) {
+        let token_client = token::Client::new(env, &escrow.token);
+        let platform_fee = match &escrow.treasury {
+            Some(treasury) => {
+                let fee = Self::bps_of(gross, escrow.platform_fee_bps);
+                if fee > 0 {
+                    token_client.transfer(&env.current_contract_address(), treasury, &fee);
+                }
+                fee
+            }
+            None => 0,
+        };
+
+        let paid = gross - platform_fee;
+        token_client.transfer(&env.current_contract_address(), &escrow.freelancer, &paid);
 
-        if arbiter != escrow.arbiter {
-             panic!("Not the authorized arbiter");
+        escrow.released_amount += paid;
+        escrow.fees_paid += platform_fee;
+        (paid, platform_fee)
+    }
+
+    // Returns (client, freelancer) parts that always add up to `locked`
+    fn split_amounts(locked: i128, split: &Split) -> Result<(i128, i128), Error> {
+        match split {
+            Split::Bps(bps) => {
+                if *bps as i128 > BPS_DENOMINATOR {
+                    return Err(Error::InvalidSplit);
+                }
+                let freelancer_amount = Self::bps_of(locked, *bps);
+                Ok((locked - freelancer_amount, freelancer_amount))
+            }
+            Split::Amounts(client_amount, freelancer_amount) => {
+                if *client_amount < 0 || *freelancer_amount < 0 {
+                    return Err(Error::InvalidSplit);
+                }
+                if client_amount.checked_add(*freelancer_amount) != Some(locked) {
+                    return Err(Error::InvalidSplit);
+                }
+                Ok((*client_amount, *freelancer_amount))
+            }
         }
+    }
 
-        if escrow.state != EscrowState::Disputed && escrow.state != EscrowState::Funded {
-             panic!("Escrow not in a state to be resolved");
+    // Appends a fresh milestone per amount and returns their total
+    fn push_milestones(milestones: &mut Vec<Milestone>, amounts: &Vec<i128>) -> Result<i128, Error> {
+        if amounts.is_empty() {
+            return Err(Error::NoMilestones);
         }
 
-        if winner != escrow.client && winner != escrow.freelancer {
-             panic!("Winner must be one of the parties");
+        let mut total: i128 = 0;
+        for milestone_amount in amounts.iter() {
+            if milestone_amount <= 0 {
+                return Err(Error::AmountMustBePositive);
+            }
+            total = total.checked_add(milestone_amount).ok_or(Error::AmountOverflow)?;
+            milestones.push_back(Milestone {
+                amount: milestone_amount,
+                approved_by_client: false,
+                approved_by_freelancer: false,
+                released: false,
+            });
         }
+        Ok(total)
+    }
 
-        // Transfer full amount to winner
-        let token_client = token::Client::new(&env, &escrow.token);
-        token_client.transfer(
-            &env.current_contract_address(),
-            &winner,
-            &escrow.amount,
-        );
-        
-        escrow.state = EscrowState::Resolved;
-        env.storage().persistent().set(&DataKey::Escrow(escrow_id), &escrow);
+    fn check_token_limits(env: &Env, token: &Address, amount: i128) -> Result<(), Error> {
+        let limits = Self::get_token_limits(env.clone(), token.clone()).ok_or(Error::TokenNotAllowed)?;
+        if limits.min_amount.is_some_and(|min| amount < min) {
+            return Err(Error::AmountBelowMinimum);
+        }
+        if limits.max_amount.is_some_and(|max| amount > max) {
+            return Err(Error::AmountAboveMaximum);
+        }
+        Ok(())
+    }
 
-        env.events().publish(
-            (symbol_short!("resolve"), arbiter, winner),
-            (escrow_id, escrow.amount),
-        );
+    // Moves an escrow into a final state: it leaves the arbiter queue and
+    // the expiry index, and a pending amendment's top-up and an unclaimed
+    // keeper bounty go back to the client
+    fn close_escrow(env: &Env, escrow_id: u64, escrow: &mut Escrow, state: EscrowState) {
+        Self::leave_arbiter_queue(env, escrow_id, escrow);
+        Self::unindex_expiry(env, escrow_id, escrow);
+        Self::drop_amendment(env, escrow_id, &escrow.token);
+        Self::return_bounty(env, escrow);
+        escrow.state = state;
+    }
+
+    // When `claim_timeout` can first succeed: the deadline, or the end of
+    // the arbitration window once disputed
+    fn claimable_after(escrow: &Escrow) -> u64 {
+        if escrow.state == EscrowState::Disputed {
+            escrow.arbitration_deadline
+        } else {
+            escrow.deadline
+        }
+    }
+
+    fn index_expiry(env: &Env, escrow_id: u64, escrow: &Escrow) {
+        let at = Self::claimable_after(escrow);
+        let day = at / 86400;
+        let extend_to = Self::escrow_ttl(env, at);
+
+        let key = DataKey::ExpiryBucket(day);
+        let mut bucket: Vec<u64> = env.storage().persistent().get(&key).unwrap_or(Vec::new(env));
+        bucket.push_back(escrow_id);
+        env.storage().persistent().set(&key, &bucket);
+        env.storage().persistent().extend_ttl(&key, extend_to - DAY_IN_LEDGERS, extend_to);
+
+        let mut days: Vec<u64> = env.storage().persistent().get(&DataKey::ExpiryDays).unwrap_or(Vec::new(env));
+        if let Err(index) = days.binary_search(day) {
+            days.insert(index, day);
+            env.storage().persistent().set(&DataKey::ExpiryDays, &days);
+        }
+        env.storage().persistent().extend_ttl(&DataKey::ExpiryDays, extend_to - DAY_IN_LEDGERS, extend_to);
     }
 
-    /// Claim timeout: if the deadline has passed and escrow is still active,
-    /// anyone can trigger a refund to the client.
-    pub fn claim_timeout(env: Env, escrow_id: u64) {
-        let mut escrow = Self::get_escrow(env.clone(), escrow_id);
+    // Call before changing anything `claimable_after` depends on
+    fn unindex_expiry(env: &Env, escrow_id: u64, escrow: &Escrow) {
+        let day = Self::claimable_after(escrow) / 86400;
+        let key = DataKey::ExpiryBucket(day);
+        let Some(mut bucket) = env.storage().persistent().get::<_, Vec<u64>>(&key) else {
+            return;
+        };
+        let Some(index) = bucket.first_index_of(escrow_id) else {
+            return;
+        };
+        bucket.remove(index);
+        if !bucket.is_empty() {
+            env.storage().persistent().set(&key, &bucket);
+            return;
+        }
 
-        if escrow.state != EscrowState::Funded && escrow.state != EscrowState::Disputed {
-            panic!("Escrow not in a claimable state");
+        env.storage().persistent().remove(&key);
+        let mut days: Vec<u64> = env.storage().persistent().get(&DataKey::ExpiryDays).unwrap_or(Vec::new(env));
+        if let Ok(index) = days.binary_search(day) {
+            days.remove(index);
+            env.storage().persistent().set(&DataKey::ExpiryDays, &days);
         }
+    }
 
-        if env.ledger().timestamp() <= escrow.deadline {
-            panic!("Deadline has not passed yet");
+    // Pays the keeper bounty to whoever settled an expired escrow,
+    // returning the amount paid. While paused, only client refunds go out,
+    // so the bounty goes back to the client instead.
+    fn pay_bounty(env: &Env, keeper: &Address, escrow: &mut Escrow) -> i128 {
+        if Self::is_paused(env.clone()) {
+            Self::return_bounty(env, escrow);
+            return 0;
+        }
+        let bounty = escrow.keeper_bounty;
+        if bounty > 0 {
+            let token_client = token::Client::new(env, &escrow.token);
+            token_client.transfer(&env.current_contract_address(), keeper, &bounty);
+            escrow.keeper_bounty = 0;
         }
+        bounty
+    }
 
-        // Refund to client
-        let token_client = token::Client::new(&env, &escrow.token);
-        token_client.transfer(
-            &env.current_contract_address(),
-            &escrow.client,
-            &escrow.amount,
-        );
+    fn publish_bounty(env: &Env, keeper: &Address, escrow_id: u64, escrow: &Escrow, bounty: i128) {
+        if bounty > 0 {
+            env.events().publish(
+                (symbol_short!("bounty"), keeper.clone()),
+                BountyEvent { header: Self::event_header(escrow_id, escrow), keeper: keeper.clone(), bounty },
+            );
+        }
+    }
+
+    fn event_header(escrow_id: u64, escrow: &Escrow) -> EventHeader {
+        EventHeader {
+            schema: EVENT_VERSION,
+            escrow_id,
+            state: escrow.state,
+            deadline: escrow.deadline,
+            token: escrow.token.clone(),
+            locked_amount: escrow.locked_amount,
+            released_amount: escrow.released_amount,
+        }
+    }
+
+    // Closing without a timeout claim: the bounty goes back to the client
+    fn return_bounty(env: &Env, escrow: &mut Escrow) {
+        if escrow.keeper_bounty > 0 {
+            let token_client = token::Client::new(env, &escrow.token);
+            token_client.transfer(&env.current_contract_address(), &escrow.client, &escrow.keeper_bounty);
+            escrow.keeper_bounty = 0;
+        }
+    }
+
+    fn check_batch_size(len: u32) -> Result<(), Error> {
+        if len == 0 || len > MAX_BATCH_SIZE {
+            return Err(Error::InvalidBatch);
+        }
+        Ok(())
+    }
+
+    // Legacy deposit settings: no anchored terms, refund fallback, lump sum
+    fn basic_options(env: &Env, panel: Vec<Address>, threshold: u32) -> EscrowOptions {
+        EscrowOptions {
+            terms_hash: BytesN::from_array(env, &[0; 32]),
+            terms_uri: None,
+            panel,
+            threshold,
+            fallback: Fallback::Refund,
+            kind: EscrowKind::LumpSum,
+            keeper_bounty: 0,
+        }
+    }
+
+    // Approves the current milestone for `approver`, releasing it once both
+    // parties have approved. Auth and pause checks are up to the caller.
+    fn approve_escrow(env: &Env, approver: &Address, escrow_id: u64) -> Result<(), Error> {
+        let mut escrow = Self::get_escrow(env.clone(), escrow_id)?;
+
+        // Streams pay out through `withdraw_vested`
+        if escrow.kind != EscrowKind::LumpSum {
+            return Err(Error::NotApprovable);
+        }
+
+        if !Self::is_open(&escrow) && escrow.state != EscrowState::Disputed {
+            return Err(Error::NotApprovable);
+        }
+
+        // A dispute can be raised before the freelancer accepts; delivery
+        // still can't be approved without acceptance
+        if escrow.accepted_at.is_none() {
+            return Err(Error::NotAccepted);
+        }
+
+        // Block approval if deadline has passed (the clock is frozen while disputed)
+        if escrow.state == EscrowState::Accepted && env.ledger().timestamp() > escrow.deadline {
+            return Err(Error::DeadlinePassed);
+        }
+
+        let index = Self::current_milestone(&escrow).ok_or(Error::NoMilestoneLeft)?;
+        let mut milestone = escrow.milestones.get(index).unwrap();
 
-        escrow.state = EscrowState::Cancelled;
-        env.storage().persistent().set(&DataKey::Escrow(escrow_id), &escrow);
+        if *approver == escrow.client {
+            milestone.approved_by_client = true;
+        } else if *approver == escrow.freelancer {
+            milestone.approved_by_freelancer = true;
+        } else {
+            return Err(Error::NotAuthorizedToApprove);
+        }
 
         env.events().publish(
-            (symbol_short!("timeout"), escrow.client.clone()),
-            (escrow_id, escrow.amount),
+            (symbol_short!("approve"), approver.clone()),
+            ApproveEvent { header: Self::event_header(escrow_id, &escrow), approver: approver.clone(), milestone: index },
         );
-    }
 
-    pub fn get_escrow(env: Env, escrow_id: u64) -> Escrow {
-        env.storage().persistent().get(&DataKey::Escrow(escrow_id)).expect("Escrow does not exist")
+        // Check for Release condition (Both approved)
+        if milestone.approved_by_client && milestone.approved_by_freelancer {
+            let (paid, platform_fee) = Self::release_to_freelancer(env, &mut escrow, milestone.amount);
+            milestone.released = true;
+            escrow.locked_amount -= milestone.amount;
+            Self::clear_votes(env, escrow_id);
+
+            if escrow.locked_amount == 0 {
+                Self::close_escrow(env, escrow_id, &mut escrow, EscrowState::Released);
+            }
+
+            env.events().publish(
+                (symbol_short!("release"), escrow.client.clone(), escrow.freelancer.clone()),
+                ReleaseEvent { header: Self::event_header(escrow_id, &escrow), milestone: index, paid, platform_fee },
+            );
+        }
+
+        escrow.milestones.set(index, milestone);
+        Self::save_escrow(env, escrow_id, &escrow);
+
+        Ok(())
     }
 
-    pub fn get_user_escrows(env: Env, user: Address) -> Vec<u64> {
-        env.storage().persistent().get(&DataKey::UserEscrows(user)).unwrap_or(Vec::new(&env))
+    // Gross amount of a stream vested at the current ledger time
+    fn vested_amount(env: &Env, escrow: &Escrow, schedule: &Vesting) -> i128 {
+        let now = env.ledger().timestamp();
+        if now < schedule.cliff {
+            0
+        } else if now >= schedule.end {
+            escrow.amount
+        } else {
+            escrow.amount * (now - schedule.start) as i128 / (schedule.end - schedule.start) as i128
+        }
     }
 
-    // Helpers
+    // Funded or accepted: neither disputed nor closed
+    fn is_open(escrow: &Escrow) -> bool {
+        matches!(escrow.state, EscrowState::Funded | EscrowState::Accepted)
+    }
 
-    fn get_next_escrow_id(env: &Env) -> u64 {
-        env.storage().instance().get(&DataKey::NextEscrowId).unwrap_or(0)
+    fn current_milestone(escrow: &Escrow) -> Option<u32> {
+        escrow.milestones.iter().position(|m| !m.released).map(|i| i as u32)
     }
 
-    fn add_escrow_to_user(env: &Env, user: Address, escrow_id: u64) {
+    fn add_escrow_to_user(env: &Env, user: Address, escrow_id: u64, deadline: u64) {
         let key = DataKey::UserEscrows(user.clone());
         let mut list: Vec<u64> = env.storage().persistent().get(&key).unwrap_or(Vec::new(env));
         list.push_back(escrow_id);
         env.storage().persistent().set(&key, &list);
+        Self::extend_user(env, &user, deadline);
+    }
+
+    fn add_to_arbiter_queue(env: &Env, arbiter: &Address, escrow_id: u64, deadline: u64) {
+        let key = DataKey::ArbiterQueue(arbiter.clone());
+        let mut queue: Vec<u64> = env.storage().persistent().get(&key).unwrap_or(Vec::new(env));
+        queue.push_back(escrow_id);
+        env.storage().persistent().set(&key, &queue);
+
+        let extend_to = Self::escrow_ttl(env, deadline).max(USER_BUMP_AMOUNT);
+        env.storage().persistent().extend_ttl(&key, extend_to - DAY_IN_LEDGERS, extend_to);
+    }
+
+    // Call before moving an escrow out of `Disputed`
+    fn leave_arbiter_queue(env: &Env, escrow_id: u64, escrow: &Escrow) {
+        if escrow.state != EscrowState::Disputed {
+            return;
+        }
+        for member in escrow.panel.iter() {
+            let key = DataKey::ArbiterQueue(member);
+            let mut queue: Vec<u64> = env.storage().persistent().get(&key).unwrap_or(Vec::new(env));
+            if let Some(index) = queue.first_index_of(escrow_id) {
+                queue.remove(index);
+                env.storage().persistent().set(&key, &queue);
+            }
+        }
+    }
+
+    fn save_votes(env: &Env, escrow_id: u64, votes: &Vec<Vote>, deadline: u64) {
+        let key = DataKey::Votes(escrow_id);
+        env.storage().persistent().set(&key, votes);
+
+        let extend_to = Self::escrow_ttl(env, deadline);
+        env.storage().persistent().extend_ttl(&key, extend_to - DAY_IN_LEDGERS, extend_to);
+    }
+
+    // Votes are checked against the locked amount when cast, so call this
+    // whenever it changes
+    fn clear_votes(env: &Env, escrow_id: u64) {
+        env.storage().persistent().remove(&DataKey::Votes(escrow_id));
+    }
+
+    fn save_escrow(env: &Env, escrow_id: u64, escrow: &Escrow) {
+        env.storage().persistent().set(&DataKey::Escrow(escrow_id), escrow);
+        Self::extend_escrow(env, escrow_id, Self::live_until(escrow));
+    }
+
+    fn extend_escrow(env: &Env, escrow_id: u64, deadline: u64) {
+        let extend_to = Self::escrow_ttl(env, deadline);
+        env.storage().persistent().extend_ttl(
+            &DataKey::Escrow(escrow_id),
+            extend_to - DAY_IN_LEDGERS,
+            extend_to,
+        );
+    }
+
+    // Removes the pending amendment, if any, refunding its top-up.
+    // Returns the amount refunded.
+    fn drop_amendment(env: &Env, escrow_id: u64, token: &Address) -> i128 {
+        let key = DataKey::Amendment(escrow_id);
+        let Some(amendment) = env.storage().persistent().get::<_, Amendment>(&key) else {
+            return 0;
+        };
+        if amendment.top_up > 0 {
+            let token_client = token::Client::new(env, token);
+            token_client.transfer(&env.current_contract_address(), &amendment.proposer, &amendment.top_up);
+        }
+        env.storage().persistent().remove(&key);
+        amendment.top_up
+    }
+
+    // Last timestamp at which the escrow can still change hands
+    fn live_until(escrow: &Escrow) -> u64 {
+        escrow.deadline.max(escrow.arbitration_deadline)
+    }
+
+    /// TTL (in ledgers) that keeps an escrow live until its deadline plus a
+    /// buffer, capped at the network's maximum TTL.
+    fn escrow_ttl(env: &Env, deadline: u64) -> u32 {
+        let remaining = deadline.saturating_sub(env.ledger().timestamp()) / LEDGER_CLOSE_SECONDS;
+        let remaining = u32::try_from(remaining).unwrap_or(u32::MAX);
+        remaining.saturating_add(ESCROW_TTL_BUFFER).min(env.storage().max_ttl())
+    }
+
+    // User indexes outlive the longest escrow they reference
+    fn extend_user(env: &Env, user: &Address, deadline: u64) {
+        let key = DataKey::UserEscrows(user.clone());
+        if env.storage().persistent().has(&key) {
+            let extend_to = Self::escrow_ttl(env, deadline).max(USER_BUMP_AMOUNT);
+            env.storage().persistent().extend_ttl(&key, extend_to - DAY_IN_LEDGERS, extend_to);
+        }
+    }
+
+    fn extend_instance(env: &Env) {
+        env.storage().instance().extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
     }
 }
 
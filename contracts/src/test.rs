@@ -1,7 +1,12 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::{Address as _, Ledger}, Env};
+extern crate std;
+
+use soroban_sdk::{
+    testutils::{storage::{Instance as _, Persistent as _}, Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger, MockAuth, MockAuthInvoke},
+    vec, Bytes, BytesN, Env, FromVal, IntoVal, Symbol, Val,
+};
 
 // Default deadline for tests: 30 days
 const TEST_DEADLINE_DAYS: u64 = 30;
@@ -23,6 +28,10 @@ fn test_flow() {
     let user_b = Address::generate(&env);
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     // Deposit (with Arbiter and Deadline)
@@ -34,6 +43,11 @@ fn test_flow() {
     assert_eq!(escrow.state, EscrowState::Funded);
     assert!(escrow.deadline > 0);
 
+    // Approval of delivery needs the freelancer to accept the job first
+    assert_eq!(client.try_approve(&user_a, &id), Err(Ok(Error::NotAccepted)));
+    client.accept(&user_b, &id, &escrow.terms_hash);
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Accepted);
+
     // Verify Indexing
     let user_a_escrows = client.get_user_escrows(&user_a);
     assert_eq!(user_a_escrows.len(), 1);
@@ -69,6 +83,10 @@ fn test_cancel() {
     let user_b = Address::generate(&env);
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     // Deposit
@@ -101,6 +119,10 @@ fn test_dispute_resolution() {
     let user_b = Address::generate(&env); // Freelancer
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     // Deposit
@@ -123,7 +145,6 @@ fn test_dispute_resolution() {
 }
 
 #[test]
-#[should_panic(expected = "Amount must be positive")]
 fn test_zero_amount_deposit() {
     let env = Env::default();
     env.mock_all_auths();
@@ -138,12 +159,16 @@ fn test_zero_amount_deposit() {
     let user_b = Address::generate(&env);
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     // Deposit 0 should fail
-    client.deposit(&user_a, &user_b, &arbiter, &token_contract, &0_i128, &TEST_DEADLINE_DAYS);
+    let result = client.try_deposit(&user_a, &user_b, &arbiter, &token_contract, &0_i128, &TEST_DEADLINE_DAYS);
+    assert_eq!(result, Err(Ok(Error::AmountMustBePositive)));
 }
 
 #[test]
-#[should_panic(expected = "Deadline must be at least 1 day")]
 fn test_zero_deadline_deposit() {
     let env = Env::default();
     env.mock_all_auths();
@@ -159,10 +184,15 @@ fn test_zero_deadline_deposit() {
     let user_b = Address::generate(&env);
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     // Deposit with 0 deadline should fail
-    client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &0);
+    let result = client.try_deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &0);
+    assert_eq!(result, Err(Ok(Error::DeadlineTooShort)));
 }
 
 #[test]
@@ -181,21 +211,25 @@ fn test_double_approval_client() {
     let user_b = Address::generate(&env);
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
 
     // Client approves twice - second should be idempotent
     client.approve(&user_a, &id);
     client.approve(&user_a, &id);
 
     let escrow = client.get_escrow(&id);
-    assert_eq!(escrow.approved_by_client, true);
-    assert_eq!(escrow.state, EscrowState::Funded); // Not released since freelancer didn't approve
+    assert!(escrow.milestones.get(0).unwrap().approved_by_client);
+    assert_eq!(escrow.state, EscrowState::Accepted); // Not released since freelancer didn't approve
 }
 
 #[test]
-#[should_panic(expected = "Only client can cancel")]
 fn test_unauthorized_cancel() {
     let env = Env::default();
     env.mock_all_auths();
@@ -211,17 +245,21 @@ fn test_unauthorized_cancel() {
     let user_b = Address::generate(&env);
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
 
     // Freelancer tries to cancel - should fail
-    client.cancel(&user_b, &id);
+    let result = client.try_cancel(&user_b, &id);
+    assert_eq!(result, Err(Ok(Error::OnlyClientCanCancel)));
 }
 
 #[test]
-#[should_panic(expected = "Cannot cancel: Freelancer has already accepted/approved")]
-fn test_cancel_after_freelancer_approval() {
+fn test_cancel_after_freelancer_acceptance() {
     let env = Env::default();
     env.mock_all_auths();
 
@@ -236,19 +274,23 @@ fn test_cancel_after_freelancer_approval() {
     let user_b = Address::generate(&env);
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
 
-    // Freelancer approves first
-    client.approve(&user_b, &id);
+    // Freelancer accepts the job
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
 
-    // Client tries to cancel after freelancer approved - should fail
-    client.cancel(&user_a, &id);
+    // Client tries to cancel after freelancer accepted - should fail
+    let result = client.try_cancel(&user_a, &id);
+    assert_eq!(result, Err(Ok(Error::FreelancerAlreadyAccepted)));
 }
 
 #[test]
-#[should_panic(expected = "Not the authorized arbiter")]
 fn test_unauthorized_resolve() {
     let env = Env::default();
     env.mock_all_auths();
@@ -265,6 +307,10 @@ fn test_unauthorized_resolve() {
     let arbiter = Address::generate(&env);
     let random = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
@@ -273,7 +319,8 @@ fn test_unauthorized_resolve() {
     client.dispute(&user_a, &id);
 
     // Random address (not arbiter) tries to resolve - should fail
-    client.resolve(&random, &id, &user_a);
+    let result = client.try_resolve(&random, &id, &user_a);
+    assert_eq!(result, Err(Ok(Error::NotArbiter)));
 }
 
 #[test]
@@ -293,6 +340,10 @@ fn test_claim_timeout() {
     let user_b = Address::generate(&env);
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     // Deposit with 1-day deadline
@@ -300,11 +351,11 @@ fn test_claim_timeout() {
 
     // Fast-forward ledger time past the deadline (2 days = 172800 seconds)
     env.ledger().with_mut(|li| {
-        li.timestamp = li.timestamp + 172800;
+        li.timestamp += 172800;
     });
 
     // Claim timeout — should refund to client
-    client.claim_timeout(&id);
+    client.claim_timeout(&user_a, &id);
 
     assert_eq!(token.balance(&user_a), 1000_i128); // Full refund
     let escrow = client.get_escrow(&id);
@@ -312,7 +363,6 @@ fn test_claim_timeout() {
 }
 
 #[test]
-#[should_panic(expected = "Deadline has not passed yet")]
 fn test_claim_timeout_before_deadline() {
     let env = Env::default();
     env.mock_all_auths();
@@ -328,11 +378,2101 @@ fn test_claim_timeout_before_deadline() {
     let user_b = Address::generate(&env);
     let arbiter = Address::generate(&env);
 
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
     token_admin_client.mint(&user_a, &1000_i128);
 
     // Deposit with 30-day deadline
     let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
 
-    // Try to claim before deadline — should panic
-    client.claim_timeout(&id);
+    // Try to claim before deadline — should fail
+    let result = client.try_claim_timeout(&user_a, &id);
+    assert_eq!(result, Err(Ok(Error::DeadlineNotPassed)));
+}
+
+#[test]
+fn test_milestone_flow() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    // Three phases: 100 + 200 + 300
+    let amounts = vec![&env, 100_i128, 200_i128, 300_i128];
+    let id = client.deposit_milestones(&user_a, &user_b, &arbiter, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.amount, 600_i128);
+    assert_eq!(escrow.locked_amount, 600_i128);
+    assert_eq!(escrow.milestones.len(), 3);
+
+    // First milestone
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+
+    let escrow = client.get_escrow(&id);
+    assert!(escrow.milestones.get(0).unwrap().released);
+    assert!(!escrow.milestones.get(1).unwrap().released);
+    assert_eq!(escrow.released_amount, 100_i128);
+    assert_eq!(escrow.locked_amount, 500_i128);
+    assert_eq!(escrow.state, EscrowState::Accepted);
+    assert_eq!(token.balance(&user_b), 100_i128);
+
+    // Second and third milestones
+    client.approve(&user_b, &id);
+    client.approve(&user_a, &id);
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.released_amount, 600_i128);
+    assert_eq!(escrow.locked_amount, 0);
+    assert_eq!(escrow.state, EscrowState::Released);
+    assert_eq!(token.balance(&user_b), 600_i128);
+}
+
+#[test]
+fn test_cancel_rejected_after_acceptance() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let amounts = vec![&env, 100_i128, 400_i128];
+    let id = client.deposit_milestones(&user_a, &user_b, &arbiter, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+
+    // Release the first milestone only
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+
+    // An accepted job can't be cancelled, even with milestones left
+    let result = client.try_cancel(&user_a, &id);
+    assert_eq!(result, Err(Ok(Error::FreelancerAlreadyAccepted)));
+
+    assert_eq!(token.balance(&user_a), 500_i128);
+    assert_eq!(token.balance(&user_b), 100_i128);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.state, EscrowState::Accepted);
+    assert_eq!(escrow.released_amount, 100_i128);
+    assert_eq!(escrow.locked_amount, 400_i128);
+}
+
+#[test]
+fn test_claim_timeout_after_partial_release() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let amounts = vec![&env, 300_i128, 200_i128];
+    let id = client.deposit_milestones(&user_a, &user_b, &arbiter, &token_contract, &amounts, &1);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+
+    env.ledger().with_mut(|li| {
+        li.timestamp += 172800;
+    });
+
+    // Only the second milestone goes back to the client
+    client.claim_timeout(&user_a, &id);
+
+    assert_eq!(token.balance(&user_a), 700_i128);
+    assert_eq!(token.balance(&user_b), 300_i128);
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Cancelled);
+}
+
+#[test]
+fn test_zero_amount_milestone() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    let amounts = vec![&env, 100_i128, 0_i128];
+    let result = client.try_deposit_milestones(&user_a, &user_b, &arbiter, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+    assert_eq!(result, Err(Ok(Error::AmountMustBePositive)));
+}
+
+#[test]
+fn test_errors_by_code() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+    let random = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    // Unknown escrow
+    assert_eq!(client.try_get_escrow(&42), Err(Ok(Error::EscrowNotFound)));
+
+    // Empty milestone list
+    let result = client.try_deposit_milestones(&user_a, &user_b, &arbiter, &token_contract, &Vec::new(&env), &TEST_DEADLINE_DAYS);
+    assert_eq!(result, Err(Ok(Error::NoMilestones)));
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+
+    assert_eq!(client.try_approve(&random, &id), Err(Ok(Error::NotAuthorizedToApprove)));
+    assert_eq!(client.try_dispute(&random, &id), Err(Ok(Error::OnlyPartiesCanDispute)));
+    assert_eq!(client.try_resolve(&arbiter, &id, &random), Err(Ok(Error::InvalidWinner)));
+
+    client.dispute(&user_b, &id);
+    assert_eq!(client.try_dispute(&user_a, &id), Err(Ok(Error::NotDisputable)));
+    assert_eq!(client.try_cancel(&user_a, &id), Err(Ok(Error::NotCancellable)));
+
+    client.resolve(&arbiter, &id, &user_b);
+    assert_eq!(client.try_approve(&user_a, &id), Err(Ok(Error::NotApprovable)));
+    assert_eq!(client.try_resolve(&arbiter, &id, &user_a), Err(Ok(Error::NotResolvable)));
+    assert_eq!(client.try_claim_timeout(&user_a, &id), Err(Ok(Error::NotClaimable)));
+
+    // Approval after the deadline
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 172800;
+    });
+    assert_eq!(client.try_approve(&user_a, &id), Err(Ok(Error::DeadlinePassed)));
+}
+
+#[test]
+fn test_ttl_scales_with_deadline() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    // 90-day escrow must outlive its deadline
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &90);
+
+    env.as_contract(&contract_id, || {
+        let escrow_ttl = env.storage().persistent().get_ttl(&DataKey::Escrow(id));
+        assert!(escrow_ttl > 90 * DAY_IN_LEDGERS);
+
+        let user_ttl = env.storage().persistent().get_ttl(&DataKey::UserEscrows(user_b.clone()));
+        assert!(user_ttl >= escrow_ttl);
+
+        assert!(env.storage().instance().get_ttl() >= INSTANCE_LIFETIME_THRESHOLD);
+    });
+}
+
+#[test]
+fn test_ttl_capped_for_far_deadline() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| {
+        li.sequence_number = 1_000;
+    });
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    // Thousands of years out: the TTL stops at the network maximum
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1_000_000);
+    let max_ttl = env.as_contract(&contract_id, || env.storage().max_ttl());
+    env.as_contract(&contract_id, || {
+        assert_eq!(env.storage().persistent().get_ttl(&DataKey::Escrow(id)), max_ttl);
+        assert_eq!(env.storage().persistent().get_ttl(&DataKey::UserEscrows(user_a.clone())), max_ttl);
+    });
+
+    // Amendment deadlines have no upper bound
+    client.propose_amendment(&user_a, &id, &Some(u64::MAX), &None);
+    client.accept_amendment(&user_b, &id);
+    assert_eq!(client.get_escrow(&id).deadline, u64::MAX);
+    env.as_contract(&contract_id, || {
+        assert_eq!(env.storage().persistent().get_ttl(&DataKey::Escrow(id)), max_ttl);
+    });
+}
+
+#[test]
+fn test_extend_escrow_ttl() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1);
+
+    // Let 25 days of ledgers pass without touching the expired escrow
+    env.ledger().with_mut(|li| {
+        li.sequence_number += 25 * DAY_IN_LEDGERS;
+        li.timestamp += 25 * 86400;
+    });
+
+    let ttl_before = env.as_contract(&contract_id, || {
+        env.storage().persistent().get_ttl(&DataKey::Escrow(id))
+    });
+
+    client.extend_escrow_ttl(&id);
+
+    let ttl_after = env.as_contract(&contract_id, || {
+        env.storage().persistent().get_ttl(&DataKey::Escrow(id))
+    });
+    assert!(ttl_after > ttl_before);
+    assert_eq!(client.try_extend_escrow_ttl(&99), Err(Ok(Error::EscrowNotFound)));
+}
+
+#[test]
+fn test_resolve_split_bps() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &1000_i128, &TEST_DEADLINE_DAYS);
+    client.dispute(&user_a, &id);
+
+    // 70% to the freelancer, 30% back to the client
+    client.resolve_split(&arbiter, &id, &Split::Bps(7000));
+
+    assert_eq!(token.balance(&user_a), 300_i128);
+    assert_eq!(token.balance(&user_b), 700_i128);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.state, EscrowState::Resolved);
+    assert_eq!(escrow.released_amount, 700_i128);
+    assert_eq!(escrow.locked_amount, 0);
+}
+
+#[test]
+fn test_resolve_split_amounts() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let amounts = vec![&env, 200_i128, 300_i128];
+    let id = client.deposit_milestones(&user_a, &user_b, &arbiter, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+
+    // First milestone paid out, dispute over the remaining 300
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+    client.dispute(&user_b, &id);
+
+    // Parts must add up to the locked amount
+    let result = client.try_resolve_split(&arbiter, &id, &Split::Amounts(100, 100));
+    assert_eq!(result, Err(Ok(Error::InvalidSplit)));
+    let result = client.try_resolve_split(&arbiter, &id, &Split::Amounts(-100, 400));
+    assert_eq!(result, Err(Ok(Error::InvalidSplit)));
+    let result = client.try_resolve_split(&arbiter, &id, &Split::Bps(10_001));
+    assert_eq!(result, Err(Ok(Error::InvalidSplit)));
+
+    client.resolve_split(&arbiter, &id, &Split::Amounts(100, 200));
+
+    assert_eq!(token.balance(&user_a), 600_i128);
+    assert_eq!(token.balance(&user_b), 400_i128);
+    assert_eq!(client.get_escrow(&id).released_amount, 400_i128);
+}
+
+#[test]
+fn test_platform_fee_on_release() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let admin = Address::generate(&env);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    // 2% platform fee, 5% arbiter fee
+    client.set_fee_config(&FeeConfig { treasury: treasury.clone(), platform_fee_bps: 200, arbiter_fee_bps: 500 });
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &1000_i128, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+
+    // Later config changes, including a new treasury, don't touch existing jobs
+    let new_treasury = Address::generate(&env);
+    client.set_fee_config(&FeeConfig { treasury: new_treasury.clone(), platform_fee_bps: 1000, arbiter_fee_bps: 1000 });
+
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+
+    assert_eq!(token.balance(&user_b), 980_i128);
+    assert_eq!(token.balance(&treasury), 20_i128);
+    assert_eq!(token.balance(&new_treasury), 0);
+    assert_eq!(token.balance(&arbiter), 0);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.platform_fee_bps, 200);
+    assert_eq!(escrow.treasury, Some(treasury));
+    assert_eq!(escrow.released_amount, 980_i128);
+    assert_eq!(escrow.fees_paid, 20_i128);
+}
+
+#[test]
+fn test_arbiter_fee_on_resolve() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+    let treasury = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+
+    token_admin_client.mint(&user_a, &2000_i128);
+
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+    client.set_fee_config(&FeeConfig { treasury: treasury.clone(), platform_fee_bps: 200, arbiter_fee_bps: 500 });
+
+    // Refund to the client: arbiter fee only
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &1000_i128, &TEST_DEADLINE_DAYS);
+    client.dispute(&user_a, &id);
+    client.resolve(&arbiter, &id, &user_a);
+
+    assert_eq!(token.balance(&arbiter), 50_i128);
+    assert_eq!(token.balance(&user_a), 1950_i128);
+    assert_eq!(token.balance(&treasury), 0);
+
+    // Split: arbiter fee off the top, platform fee on the freelancer's part
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &1000_i128, &TEST_DEADLINE_DAYS);
+    client.dispute(&user_b, &id);
+
+    let result = client.try_resolve_split(&arbiter, &id, &Split::Amounts(500, 500));
+    assert_eq!(result, Err(Ok(Error::InvalidSplit)));
+
+    client.resolve_split(&arbiter, &id, &Split::Amounts(450, 500));
+
+    assert_eq!(token.balance(&arbiter), 100_i128);
+    assert_eq!(token.balance(&user_a), 1400_i128);
+    assert_eq!(token.balance(&user_b), 490_i128);
+    assert_eq!(token.balance(&treasury), 10_i128);
+    assert_eq!(client.get_escrow(&id).fees_paid, 60_i128);
+}
+
+#[test]
+fn test_fee_config_bounds() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let treasury = Address::generate(&env);
+    let admin = Address::generate(&env);
+
+    assert_eq!(client.get_fee_config(), None);
+
+    // Fees can't be configured before an admin exists
+    let config = FeeConfig { treasury: treasury.clone(), platform_fee_bps: 100, arbiter_fee_bps: 300 };
+    assert_eq!(client.try_set_fee_config(&config), Err(Ok(Error::NotInitialized)));
+
+    client.initialize(&admin);
+
+    let result = client.try_set_fee_config(&FeeConfig { treasury: treasury.clone(), platform_fee_bps: 5000, arbiter_fee_bps: 0 });
+    assert_eq!(result, Err(Ok(Error::InvalidFee)));
+
+    let config = FeeConfig { treasury, platform_fee_bps: 100, arbiter_fee_bps: 300 };
+    client.set_fee_config(&config);
+    assert_eq!(client.get_fee_config(), Some(config));
+}
+
+#[test]
+fn test_migration_pending() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+    token_admin_client.mint(&user_a, &1000_i128);
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+
+    // As after an upgrade to code with a newer storage version
+    env.as_contract(&contract_id, || {
+        env.storage().instance().set(&DataKey::StorageVersion, &(STORAGE_VERSION - 1));
+    });
+    assert_eq!(client.try_get_escrow(&id), Err(Ok(Error::MigrationPending)));
+    assert_eq!(client.try_approve(&user_a, &id), Err(Ok(Error::MigrationPending)));
+
+    assert_eq!(client.migrate(), STORAGE_VERSION);
+    assert_eq!(client.get_storage_version(), STORAGE_VERSION);
+    assert_eq!(client.get_escrow(&id).locked_amount, 100);
+}
+
+#[test]
+fn test_initialize_and_transfer_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+
+    assert_eq!(client.try_get_admin(), Err(Ok(Error::NotInitialized)));
+    assert_eq!(client.try_accept_admin(), Err(Ok(Error::NoPendingAdmin)));
+
+    client.initialize(&admin);
+    assert_eq!(client.get_admin(), admin);
+    assert_eq!(client.get_storage_version(), STORAGE_VERSION);
+    assert_eq!(client.try_initialize(&new_admin), Err(Ok(Error::AlreadyInitialized)));
+
+    // Handover only takes effect once the new admin accepts
+    client.transfer_admin(&new_admin);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    contract_id.clone(),
+                    Symbol::new(&env, "transfer_admin"),
+                    (new_admin.clone(),).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert_eq!(client.get_admin(), admin);
+
+    client.accept_admin();
+    assert_eq!(client.get_admin(), new_admin);
+    assert_eq!(client.try_accept_admin(), Err(Ok(Error::NoPendingAdmin)));
+
+    assert_eq!(client.migrate(), STORAGE_VERSION);
+}
+
+#[test]
+fn test_upgrade_requires_admin() {
+    let env = Env::default();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let wasm_hash = BytesN::from_array(&env, &[0; 32]);
+    assert_eq!(client.try_upgrade(&wasm_hash), Err(Ok(Error::NotInitialized)));
+
+    // The admin alone can't claim the contract; the deployer has to sign too
+    let admin = Address::generate(&env);
+    let result = client
+        .mock_auths(&[MockAuth {
+            address: &admin,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "initialize",
+                args: (admin.clone(),).into_val(&env),
+                sub_invokes: &[],
+            },
+        }])
+        .try_initialize(&admin);
+    assert!(result.is_err());
+
+    client.mock_all_auths().initialize(&admin);
+
+    // No admin signature
+    assert!(client.try_upgrade(&wasm_hash).is_err());
+    assert!(client.try_migrate().is_err());
+}
+
+#[test]
+fn test_pause_blocks_transfers() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+    let admin = Address::generate(&env);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+    let disputed = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    client.dispute(&user_b, &disputed);
+
+    client.pause();
+    assert!(client.is_paused());
+
+    let result = client.try_deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    assert_eq!(result, Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_approve(&user_a, &id), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_resolve(&arbiter, &disputed, &user_b), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_resolve_split(&arbiter, &disputed, &Split::Bps(5000)), Err(Ok(Error::ContractPaused)));
+    assert_eq!(client.try_cancel(&user_a, &id), Err(Ok(Error::ContractPaused)));
+
+    client.unpause();
+    assert!(!client.is_paused());
+
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+    assert_eq!(token.balance(&user_b), 100_i128);
+}
+
+#[test]
+fn test_pause_grace_period_refunds() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+    let admin = Address::generate(&env);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &300_i128, &1);
+    let expired = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &200_i128, &1);
+
+    client.pause();
+
+    env.ledger().with_mut(|li| {
+        li.timestamp += 2 * 86400;
+    });
+
+    // Still inside the grace period
+    assert_eq!(client.try_claim_timeout(&user_a, &expired), Err(Ok(Error::ContractPaused)));
+
+    env.ledger().with_mut(|li| {
+        li.timestamp += PAUSE_GRACE_PERIOD;
+    });
+
+    // Client can get funds out, but nothing can be released to the freelancer
+    client.cancel(&user_a, &id);
+    client.claim_timeout(&user_a, &expired);
+    assert_eq!(token.balance(&user_a), 1000_i128);
+    assert!(client.is_paused());
+}
+
+#[test]
+fn test_token_allowlist() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin.clone()).address();
+    let other_token = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+    let admin = Address::generate(&env);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    client.initialize(&admin);
+    assert_eq!(client.get_allowed_tokens().len(), 0);
+
+    let result = client.try_deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    assert_eq!(result, Err(Ok(Error::TokenNotAllowed)));
+
+    assert_eq!(client.try_allow_token(&token_contract, &Some(100), &Some(50)), Err(Ok(Error::InvalidTokenLimits)));
+    assert_eq!(client.try_allow_token(&token_contract, &Some(0), &None), Err(Ok(Error::InvalidTokenLimits)));
+
+    client.allow_token(&token_contract, &Some(50), &Some(500));
+    client.allow_token(&other_token, &None, &None);
+
+    let tokens = client.get_allowed_tokens();
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(
+        client.get_token_limits(&token_contract),
+        Some(TokenLimits { min_amount: Some(50), max_amount: Some(500) })
+    );
+
+    let result = client.try_deposit(&user_a, &user_b, &arbiter, &token_contract, &10_i128, &TEST_DEADLINE_DAYS);
+    assert_eq!(result, Err(Ok(Error::AmountBelowMinimum)));
+    let result = client.try_deposit(&user_a, &user_b, &arbiter, &token_contract, &600_i128, &TEST_DEADLINE_DAYS);
+    assert_eq!(result, Err(Ok(Error::AmountAboveMaximum)));
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+
+    // Removing a token only blocks new deposits
+    client.remove_token(&token_contract);
+    assert_eq!(client.get_token_limits(&token_contract), None);
+    assert_eq!(client.try_remove_token(&token_contract), Err(Ok(Error::TokenNotAllowed)));
+
+    let result = client.try_deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    assert_eq!(result, Err(Ok(Error::TokenNotAllowed)));
+
+    client.cancel(&user_a, &id);
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Cancelled);
+}
+
+#[test]
+fn test_user_escrows_page() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &10_000_i128);
+    token_admin_client.mint(&user_b, &10_000_i128);
+
+    // user_a is client on 5 escrows and freelancer on 2
+    for _ in 0..5 {
+        client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    }
+    for _ in 0..2 {
+        client.deposit(&user_b, &user_a, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    }
+    client.cancel(&user_a, &1);
+    client.cancel(&user_a, &3);
+
+    // Walk every page
+    let page = client.get_user_escrows_page(&user_a, &None, &None, &0, &3);
+    assert_eq!(page.escrows.len(), 3);
+    assert_eq!(page.escrows.get(0).unwrap().id, 0);
+    assert_eq!(page.next_cursor, Some(3));
+
+    let page = client.get_user_escrows_page(&user_a, &None, &None, &3, &3);
+    assert_eq!(page.escrows.len(), 3);
+    assert_eq!(page.next_cursor, Some(6));
+
+    let page = client.get_user_escrows_page(&user_a, &None, &None, &6, &3);
+    assert_eq!(page.escrows.len(), 1);
+    assert_eq!(page.escrows.get(0).unwrap().id, 6);
+    assert_eq!(page.next_cursor, None);
+
+    // Filter by state
+    let page = client.get_user_escrows_page(&user_a, &Some(EscrowState::Cancelled), &None, &0, &10);
+    assert_eq!(page.escrows.len(), 2);
+    assert_eq!(page.escrows.get(1).unwrap().id, 3);
+    assert_eq!(page.escrows.get(1).unwrap().escrow.state, EscrowState::Cancelled);
+
+    // Filter by role, combined with state
+    let page = client.get_user_escrows_page(&user_a, &Some(EscrowState::Funded), &Some(Role::Freelancer), &0, &10);
+    assert_eq!(page.escrows.len(), 2);
+    assert_eq!(page.escrows.get(0).unwrap().id, 5);
+    assert_eq!(page.next_cursor, None);
+
+    // Unknown user
+    let page = client.get_user_escrows_page(&Address::generate(&env), &None, &None, &0, &10);
+    assert_eq!(page.escrows.len(), 0);
+    assert_eq!(page.next_cursor, None);
+}
+
+#[test]
+fn test_arbiter_index_and_queue() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let resolved = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1);
+    let timed_out = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1);
+    let released = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1);
+    let undisputed = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1);
+
+    // The arbiter can discover every escrow they are named on
+    assert_eq!(client.get_user_escrows(&arbiter).len(), 4);
+    let page = client.get_user_escrows_page(&arbiter, &None, &Some(Role::Arbiter), &0, &10);
+    assert_eq!(page.escrows.len(), 4);
+    assert_eq!(client.get_arbiter_queue(&arbiter).len(), 0);
+
+    client.accept(&user_b, &released, &client.get_escrow(&released).terms_hash);
+
+    client.dispute(&user_a, &resolved);
+    client.dispute(&user_b, &timed_out);
+    client.dispute(&user_a, &released);
+
+    let queue = client.get_arbiter_queue(&arbiter);
+    assert_eq!(queue.len(), 3);
+    assert_eq!(queue.get(0).unwrap().id, resolved);
+    assert_eq!(queue.get(0).unwrap().escrow.state, EscrowState::Disputed);
+
+    client.resolve(&arbiter, &resolved, &user_a);
+
+    // Parties can still settle a disputed escrow by mutual approval, once
+    // the freelancer has accepted
+    client.approve(&user_a, &released);
+    client.approve(&user_b, &released);
+
+    let queue = client.get_arbiter_queue(&arbiter);
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue.get(0).unwrap().id, timed_out);
+
+    env.ledger().with_mut(|li| {
+        li.timestamp += 172800;
+    });
+    client.claim_timeout(&user_a, &undisputed);
+
+    // The disputed one only times out once the arbitration window closes
+    assert_eq!(client.get_arbiter_queue(&arbiter).len(), 1);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 14 * 86400;
+    });
+    client.claim_timeout(&user_a, &timed_out);
+
+    assert_eq!(client.get_arbiter_queue(&arbiter).len(), 0);
+}
+
+#[test]
+fn test_panel_vote_settles_at_threshold() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arb_1 = Address::generate(&env);
+    let arb_2 = Address::generate(&env);
+    let arb_3 = Address::generate(&env);
+    let treasury = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+    client.set_fee_config(&FeeConfig { treasury, platform_fee_bps: 0, arbiter_fee_bps: 600 });
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    // 2-of-3 panel
+    let panel = vec![&env, arb_1.clone(), arb_2.clone(), arb_3.clone()];
+    let amounts = vec![&env, 1000_i128];
+    let id = client.deposit_with_panel(&user_a, &user_b, &panel, &2, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.arbiter, arb_1);
+    assert_eq!(escrow.threshold, 2);
+    assert_eq!(client.get_user_escrows(&arb_3).len(), 1);
+
+    // Votes only count once the escrow is disputed
+    assert_eq!(client.try_vote(&arb_1, &id, &Split::Bps(10_000)), Err(Ok(Error::NotResolvable)));
+
+    client.dispute(&user_a, &id);
+    assert_eq!(client.get_arbiter_queue(&arb_2).len(), 1);
+
+    // A single panel member can't decide alone
+    assert_eq!(client.try_resolve(&arb_1, &id, &user_b), Err(Ok(Error::PanelVoteRequired)));
+    assert_eq!(client.try_vote(&user_a, &id, &Split::Bps(0)), Err(Ok(Error::NotArbiter)));
+
+    // Disagreeing votes don't settle
+    assert!(!client.vote(&arb_1, &id, &Split::Bps(10_000)));
+    assert!(!client.vote(&arb_2, &id, &Split::Bps(5_000)));
+
+    // A member can change their vote
+    assert!(!client.vote(&arb_2, &id, &Split::Bps(2_500)));
+    assert_eq!(client.get_votes(&id).len(), 2);
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Disputed);
+
+    // Second matching vote settles; fee is shared by the agreeing members
+    assert!(client.vote(&arb_3, &id, &Split::Bps(10_000)));
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.state, EscrowState::Resolved);
+    assert_eq!(token.balance(&user_b), 940_i128);
+    assert_eq!(token.balance(&arb_1), 30_i128);
+    assert_eq!(token.balance(&arb_3), 30_i128);
+    assert_eq!(token.balance(&arb_2), 0);
+    assert_eq!(client.get_arbiter_queue(&arb_2).len(), 0);
+
+    let votes = client.get_votes(&id);
+    assert_eq!(votes.len(), 3);
+    assert_eq!(votes.get(1).unwrap().arbiter, arb_2);
+    assert_eq!(votes.get(1).unwrap().outcome, Split::Bps(2_500));
+
+    assert_eq!(client.try_vote(&arb_2, &id, &Split::Bps(10_000)), Err(Ok(Error::NotResolvable)));
+}
+
+#[test]
+fn test_panel_votes_cleared_on_release() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arb_1 = Address::generate(&env);
+    let arb_2 = Address::generate(&env);
+    let arb_3 = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let panel = vec![&env, arb_1.clone(), arb_2.clone(), arb_3.clone()];
+    let amounts = vec![&env, 500_i128, 500_i128];
+    let id = client.deposit_with_panel(&user_a, &user_b, &panel, &2, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+
+    client.dispute(&user_a, &id);
+    assert!(!client.vote(&arb_1, &id, &Split::Amounts(500, 500)));
+
+    // Releasing a milestone mid-dispute shrinks the locked amount, so the
+    // earlier vote no longer applies
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+    assert_eq!(client.get_escrow(&id).locked_amount, 500);
+    assert_eq!(client.get_votes(&id).len(), 0);
+
+    assert_eq!(client.try_vote(&arb_2, &id, &Split::Amounts(500, 500)), Err(Ok(Error::InvalidSplit)));
+    assert!(!client.vote(&arb_1, &id, &Split::Amounts(250, 250)));
+    assert!(client.vote(&arb_2, &id, &Split::Amounts(250, 250)));
+
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Resolved);
+    assert_eq!(token.balance(&user_a), 250);
+    assert_eq!(token.balance(&user_b), 750);
+}
+
+#[test]
+fn test_invalid_panel() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arb_1 = Address::generate(&env);
+    let arb_2 = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let amounts = vec![&env, 100_i128];
+    let cases = [
+        (Vec::new(&env), 1),
+        (vec![&env, arb_1.clone(), arb_2.clone()], 0),
+        (vec![&env, arb_1.clone(), arb_2.clone()], 3),
+        (vec![&env, arb_1.clone(), arb_1.clone()], 1),
+        (vec![&env, arb_1.clone(), user_b.clone()], 1),
+    ];
+    for (panel, threshold) in cases.iter() {
+        let result = client.try_deposit_with_panel(&user_a, &user_b, panel, threshold, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+        assert_eq!(result, Err(Ok(Error::InvalidPanel)));
+    }
+}
+
+#[test]
+fn test_submit_evidence() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+
+    let cid = Bytes::from_slice(&env, b"bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi");
+    let digest = Bytes::from_array(&env, &[7; 32]);
+
+    // Only while disputed
+    assert_eq!(client.try_submit_evidence(&user_a, &id, &cid), Err(Ok(Error::NotDisputed)));
+
+    client.dispute(&user_a, &id);
+
+    assert_eq!(client.try_submit_evidence(&arbiter, &id, &cid), Err(Ok(Error::NotParty)));
+    assert_eq!(client.try_submit_evidence(&user_a, &id, &Bytes::new(&env)), Err(Ok(Error::InvalidEvidence)));
+
+    env.ledger().with_mut(|li| {
+        li.timestamp = 1_000;
+    });
+    client.submit_evidence(&user_a, &id, &cid);
+    client.submit_evidence(&user_b, &id, &digest);
+
+    let evidence = client.get_evidence(&id);
+    assert_eq!(evidence.len(), 2);
+    assert_eq!(
+        evidence.get(0).unwrap(),
+        Evidence { submitter: user_a.clone(), hash: cid.clone(), timestamp: 1_000 }
+    );
+    assert_eq!(evidence.get(1).unwrap().submitter, user_b);
+
+    // Per-party cap
+    for _ in 1..MAX_EVIDENCE_PER_PARTY {
+        client.submit_evidence(&user_a, &id, &digest);
+    }
+    assert_eq!(client.try_submit_evidence(&user_a, &id, &digest), Err(Ok(Error::EvidenceLimitReached)));
+    client.submit_evidence(&user_b, &id, &cid);
+
+    // Evidence stays on record after resolution
+    client.resolve(&arbiter, &id, &user_b);
+    assert_eq!(client.get_evidence(&id).len(), MAX_EVIDENCE_PER_PARTY + 2);
+    assert_eq!(client.try_submit_evidence(&user_b, &id, &cid), Err(Ok(Error::NotDisputed)));
+}
+
+#[test]
+fn test_dispute_freezes_deadline() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &200_i128, &1);
+    let late = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &200_i128, &1);
+    client.dispute(&user_b, &id);
+
+    // Past the original deadline, the dispute still blocks a timeout refund
+    env.ledger().with_mut(|li| {
+        li.timestamp += 172800;
+    });
+    let result = client.try_claim_timeout(&user_a, &id);
+    assert_eq!(result, Err(Ok(Error::ArbitrationWindowOpen)));
+
+    // A dispute can't be raised once the deadline has passed
+    let result = client.try_dispute(&user_b, &late);
+    assert_eq!(result, Err(Ok(Error::DeadlinePassed)));
+
+    // The arbiter can still decide after the original deadline
+    client.resolve(&arbiter, &id, &user_b);
+    assert_eq!(token.balance(&user_b), 200_i128);
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Resolved);
+}
+
+#[test]
+fn test_arbitration_fallback() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let options = |fallback| EscrowOptions {
+        terms_hash: BytesN::from_array(&env, &[0; 32]),
+        terms_uri: None,
+        panel: vec![&env, arbiter.clone()],
+        threshold: 1,
+        fallback,
+        kind: EscrowKind::LumpSum,
+        keeper_bounty: 0,
+    };
+    let amounts = vec![&env, 100_i128];
+
+    let result = client.try_deposit_with_options(
+        &user_a, &user_b, &token_contract, &amounts, &TEST_DEADLINE_DAYS, &options(Fallback::Split(10_001)),
+    );
+    assert_eq!(result, Err(Ok(Error::InvalidSplit)));
+
+    let refund = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    let release = client.deposit_with_options(
+        &user_a, &user_b, &token_contract, &amounts, &TEST_DEADLINE_DAYS, &options(Fallback::Release),
+    );
+    let split = client.deposit_with_options(
+        &user_a, &user_b, &token_contract, &amounts, &TEST_DEADLINE_DAYS, &options(Fallback::Split(3000)),
+    );
+    assert_eq!(client.get_escrow(&release).fallback, Fallback::Release);
+
+    client.dispute(&user_a, &refund);
+    client.dispute(&user_a, &release);
+    client.dispute(&user_b, &split);
+    assert_eq!(token.balance(&user_a), 700_i128);
+
+    let escrow = client.get_escrow(&refund);
+    assert_eq!(escrow.arbitration_deadline, env.ledger().timestamp() + 14 * 86400);
+
+    // The arbiter never decides
+    env.ledger().with_mut(|li| {
+        li.timestamp += 15 * 86400;
+    });
+    client.claim_timeout(&user_a, &refund);
+    client.claim_timeout(&user_a, &release);
+    client.claim_timeout(&user_a, &split);
+
+    assert_eq!(token.balance(&user_a), 870_i128);
+    assert_eq!(token.balance(&user_b), 130_i128);
+    assert_eq!(token.balance(&arbiter), 0_i128);
+    assert_eq!(client.get_escrow(&split).state, EscrowState::Resolved);
+    assert_eq!(client.get_arbiter_queue(&arbiter).len(), 0);
+
+    let result = client.try_claim_timeout(&user_a, &split);
+    assert_eq!(result, Err(Ok(Error::NotClaimable)));
+}
+
+#[test]
+fn test_amendment_flow() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &200_i128, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.version, 0);
+
+    // Freelancer asks for more time and more money; client accepts and tops up
+    let new_deadline = escrow.deadline + 10 * 86400;
+    client.propose_amendment(&user_b, &id, &Some(new_deadline), &Some(vec![&env, 150_i128, 150_i128]));
+    assert_eq!(token.balance(&user_a), 800_i128);
+
+    let result = client.try_accept_amendment(&user_b, &id);
+    assert_eq!(result, Err(Ok(Error::InvalidAmendment)));
+
+    client.accept_amendment(&user_a, &id);
+    assert_eq!(token.balance(&user_a), 700_i128);
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.version, 1);
+    assert_eq!(escrow.deadline, new_deadline);
+    assert_eq!(escrow.amount, 300_i128);
+    assert_eq!(escrow.locked_amount, 300_i128);
+    assert_eq!(escrow.milestones.len(), 2);
+    assert_eq!(client.get_amendment(&id), None);
+
+    let result = client.try_accept_amendment(&user_a, &id);
+    assert_eq!(result, Err(Ok(Error::NoPendingAmendment)));
+
+    // Client proposes an increase: paid in up front, returned on withdrawal
+    client.propose_amendment(&user_a, &id, &None, &Some(vec![&env, 500_i128]));
+    assert_eq!(token.balance(&user_a), 500_i128);
+    assert_eq!(client.get_amendment(&id).unwrap().top_up, 200_i128);
+    client.withdraw_amendment(&user_a, &id);
+    assert_eq!(token.balance(&user_a), 700_i128);
+
+    // Client proposes a decrease; the difference comes back on acceptance
+    client.propose_amendment(&user_a, &id, &None, &Some(vec![&env, 100_i128]));
+    client.accept_amendment(&user_b, &id);
+    assert_eq!(token.balance(&user_a), 900_i128);
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.version, 2);
+    assert_eq!(escrow.amount, 100_i128);
+    assert_eq!(escrow.deadline, new_deadline);
+
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+    assert_eq!(token.balance(&user_b), 100_i128);
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Released);
+}
+
+#[test]
+fn test_close_refunds_amendment_top_up() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    // Cancelled with a pending increase: deposit and top-up both come back
+    let cancelled = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &200_i128, &TEST_DEADLINE_DAYS);
+    client.propose_amendment(&user_a, &cancelled, &None, &Some(vec![&env, 300_i128]));
+    assert_eq!(token.balance(&user_a), 700_i128);
+    client.cancel(&user_a, &cancelled);
+    assert_eq!(token.balance(&user_a), 1000_i128);
+    assert_eq!(client.get_amendment(&cancelled), None);
+
+    // Same when a dispute settles the escrow instead
+    let resolved = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &200_i128, &TEST_DEADLINE_DAYS);
+    client.propose_amendment(&user_a, &resolved, &None, &Some(vec![&env, 300_i128]));
+    client.dispute(&user_b, &resolved);
+    client.resolve(&arbiter, &resolved, &user_b);
+    assert_eq!(token.balance(&user_a), 800_i128);
+    assert_eq!(token.balance(&user_b), 200_i128);
+    assert_eq!(token.balance(&contract_id), 0);
+    assert_eq!(client.get_amendment(&resolved), None);
+}
+
+#[test]
+fn test_invalid_amendment() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &Some(500_i128));
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let amounts = vec![&env, 100_i128, 100_i128];
+    let id = client.deposit_milestones(&user_a, &user_b, &arbiter, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+
+    let result = client.try_propose_amendment(&arbiter, &id, &Some(1), &None);
+    assert_eq!(result, Err(Ok(Error::NotParty)));
+    let result = client.try_propose_amendment(&user_b, &id, &None, &None);
+    assert_eq!(result, Err(Ok(Error::InvalidAmendment)));
+    let result = client.try_propose_amendment(&user_b, &id, &Some(env.ledger().timestamp()), &None);
+    assert_eq!(result, Err(Ok(Error::DeadlineTooShort)));
+    let result = client.try_propose_amendment(&user_b, &id, &None, &Some(vec![&env, 0_i128]));
+    assert_eq!(result, Err(Ok(Error::AmountMustBePositive)));
+    let result = client.try_propose_amendment(&user_b, &id, &None, &Some(vec![&env, 600_i128]));
+    assert_eq!(result, Err(Ok(Error::AmountAboveMaximum)));
+
+    // Releasing a milestone makes a pending amendment stale
+    client.propose_amendment(&user_b, &id, &None, &Some(vec![&env, 150_i128, 150_i128]));
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+    let result = client.try_accept_amendment(&user_a, &id);
+    assert_eq!(result, Err(Ok(Error::InvalidAmendment)));
+    let result = client.try_withdraw_amendment(&user_a, &id);
+    assert_eq!(result, Err(Ok(Error::NotParty)));
+    client.withdraw_amendment(&user_b, &id);
+    assert_eq!(client.get_amendment(&id), None);
+}
+
+#[test]
+fn test_approve_requires_acceptance() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    // Disputed straight from `Funded`: the freelancer never took the job
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    client.dispute(&user_a, &id);
+    assert_eq!(client.get_escrow(&id).accepted_at, None);
+    assert_eq!(client.try_approve(&user_a, &id), Err(Ok(Error::NotAccepted)));
+    assert_eq!(client.try_approve(&user_b, &id), Err(Ok(Error::NotAccepted)));
+    assert_eq!(token.balance(&user_b), 0);
+
+    // Accepted before the dispute: delivery can still be approved
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+    assert_eq!(client.get_escrow(&id).accepted_at, Some(env.ledger().timestamp()));
+    client.dispute(&user_a, &id);
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Released);
+    assert_eq!(token.balance(&user_b), 100_i128);
+}
+
+#[test]
+fn test_terms_hash_acceptance() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let terms = Bytes::from_slice(&env, b"Logo design, 3 revisions, SVG + PNG");
+    let terms_hash: BytesN<32> = env.crypto().sha256(&terms).into();
+    let uri = Bytes::from_slice(&env, b"ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi");
+    let mut options = EscrowOptions {
+        terms_hash: terms_hash.clone(),
+        terms_uri: Some(Bytes::new(&env)),
+        panel: vec![&env, arbiter.clone()],
+        threshold: 1,
+        fallback: Fallback::Refund,
+        kind: EscrowKind::LumpSum,
+        keeper_bounty: 0,
+    };
+    let amounts = vec![&env, 100_i128];
+
+    let result = client.try_deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &TEST_DEADLINE_DAYS, &options);
+    assert_eq!(result, Err(Ok(Error::InvalidTermsUri)));
+
+    options.terms_uri = Some(uri.clone());
+    let id = client.deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &TEST_DEADLINE_DAYS, &options);
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.terms_hash, terms_hash);
+    assert_eq!(escrow.terms_uri, Some(uri));
+    assert_eq!(escrow.state, EscrowState::Funded);
+
+    // The freelancer has to commit to the exact terms the client anchored
+    let other_terms = BytesN::from_array(&env, &[1; 32]);
+    let result = client.try_accept(&user_b, &id, &other_terms);
+    assert_eq!(result, Err(Ok(Error::TermsMismatch)));
+    let result = client.try_accept(&arbiter, &id, &terms_hash);
+    assert_eq!(result, Err(Ok(Error::NotFreelancer)));
+
+    client.accept(&user_b, &id, &terms_hash);
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Accepted);
+
+    // Escrows created without terms carry an all-zero hash
+    let plain = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    assert_eq!(client.get_escrow(&plain).terms_hash, BytesN::from_array(&env, &[0; 32]));
+}
+
+#[test]
+fn test_freelancer_decline() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    // Declining an offered job
+    let offered = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    let result = client.try_decline(&user_a, &offered);
+    assert_eq!(result, Err(Ok(Error::NotFreelancer)));
+    client.decline(&user_b, &offered);
+    assert_eq!(client.get_escrow(&offered).state, EscrowState::Cancelled);
+    assert_eq!(token.balance(&user_a), 1000_i128);
+
+    // Refunding what's left of an accepted job after a partial release
+    let amounts = vec![&env, 100_i128, 300_i128];
+    let id = client.deposit_milestones(&user_a, &user_b, &arbiter, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+    client.approve(&user_a, &id);
+    client.approve(&user_b, &id);
+    client.dispute(&user_a, &id);
+
+    client.decline(&user_b, &id);
+    assert_eq!(token.balance(&user_a), 900_i128);
+    assert_eq!(token.balance(&user_b), 100_i128);
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.state, EscrowState::Cancelled);
+    assert_eq!(escrow.locked_amount, 0);
+    assert_eq!(client.get_arbiter_queue(&arbiter).len(), 0);
+
+    let result = client.try_decline(&user_b, &id);
+    assert_eq!(result, Err(Ok(Error::NotCancellable)));
+}
+
+#[test]
+fn test_settlement_offer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+    let treasury = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+    client.set_fee_config(&FeeConfig { treasury: treasury.clone(), platform_fee_bps: 100, arbiter_fee_bps: 500 });
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let id = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &1000_i128, &TEST_DEADLINE_DAYS);
+
+    // Offers only exist for disputed escrows
+    let result = client.try_offer_settlement(&user_a, &id, &Split::Bps(5000));
+    assert_eq!(result, Err(Ok(Error::NotDisputed)));
+
+    client.dispute(&user_a, &id);
+    let result = client.try_offer_settlement(&arbiter, &id, &Split::Bps(5000));
+    assert_eq!(result, Err(Ok(Error::NotParty)));
+    let result = client.try_offer_settlement(&user_a, &id, &Split::Amounts(500, 400));
+    assert_eq!(result, Err(Ok(Error::InvalidSplit)));
+    let result = client.try_accept_settlement(&user_b, &id);
+    assert_eq!(result, Err(Ok(Error::NoSettlementOffer)));
+
+    client.offer_settlement(&user_a, &id, &Split::Bps(3000));
+    // The freelancer counters; the latest offer replaces the first
+    client.offer_settlement(&user_b, &id, &Split::Amounts(400, 600));
+    assert_eq!(client.get_settlement_offer(&id).unwrap().proposer, user_b);
+
+    let result = client.try_accept_settlement(&user_b, &id);
+    assert_eq!(result, Err(Ok(Error::InvalidSettlement)));
+
+    client.accept_settlement(&user_a, &id);
+
+    // No arbiter fee; the platform fee still applies to the freelancer's part
+    assert_eq!(token.balance(&user_a), 400_i128);
+    assert_eq!(token.balance(&user_b), 594_i128);
+    assert_eq!(token.balance(&treasury), 6_i128);
+    assert_eq!(token.balance(&arbiter), 0_i128);
+
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.state, EscrowState::Settled);
+    assert_eq!(escrow.locked_amount, 0);
+    assert_eq!(client.get_settlement_offer(&id), None);
+    assert_eq!(client.get_arbiter_queue(&arbiter).len(), 0);
+
+    let result = client.try_resolve(&arbiter, &id, &user_a);
+    assert_eq!(result, Err(Ok(Error::NotResolvable)));
+}
+
+#[test]
+fn test_vesting_stream() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &10_000_i128);
+
+    // 30-day stream with a 10-day cliff
+    let now = env.ledger().timestamp();
+    let mut options = EscrowOptions {
+        terms_hash: BytesN::from_array(&env, &[0; 32]),
+        terms_uri: None,
+        panel: vec![&env, arbiter.clone()],
+        threshold: 1,
+        fallback: Fallback::Refund,
+        kind: EscrowKind::Stream(Vesting { start: now, cliff: now + 10 * 86400, end: now + 40 * 86400 }),
+        keeper_bounty: 0,
+    };
+    let amounts = vec![&env, 3000_i128];
+
+    // The stream has to finish vesting before the deadline
+    let result = client.try_deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &TEST_DEADLINE_DAYS, &options);
+    assert_eq!(result, Err(Ok(Error::InvalidVesting)));
+    options.kind = EscrowKind::Stream(Vesting { start: now, cliff: now + 10 * 86400, end: now + 30 * 86400 });
+    let result = client.try_deposit_with_options(&user_a, &user_b, &token_contract, &vec![&env, 1000_i128, 2000_i128], &TEST_DEADLINE_DAYS, &options);
+    assert_eq!(result, Err(Ok(Error::InvalidVesting)));
+
+    let id = client.deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &TEST_DEADLINE_DAYS, &options);
+    let result = client.try_withdraw_vested(&user_b, &id);
+    assert_eq!(result, Err(Ok(Error::NotAccepted)));
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+    assert_eq!(client.try_approve(&user_a, &id), Err(Ok(Error::NotApprovable)));
+    let result = client.try_withdraw_vested(&user_a, &id);
+    assert_eq!(result, Err(Ok(Error::NotFreelancer)));
+
+    // Nothing before the cliff
+    env.ledger().with_mut(|li| {
+        li.timestamp = now + 5 * 86400;
+    });
+    let result = client.try_withdraw_vested(&user_b, &id);
+    assert_eq!(result, Err(Ok(Error::NothingToWithdraw)));
+
+    // Halfway through: half has vested
+    env.ledger().with_mut(|li| {
+        li.timestamp = now + 15 * 86400;
+    });
+    assert_eq!(client.withdraw_vested(&user_b, &id), 1500_i128);
+    assert_eq!(token.balance(&user_b), 1500_i128);
+    let result = client.try_withdraw_vested(&user_b, &id);
+    assert_eq!(result, Err(Ok(Error::NothingToWithdraw)));
+
+    // Client stops the stream: vested part to the freelancer, the rest refunded
+    env.ledger().with_mut(|li| {
+        li.timestamp = now + 20 * 86400;
+    });
+    client.stop_stream(&user_a, &id);
+    assert_eq!(token.balance(&user_b), 2000_i128);
+    assert_eq!(token.balance(&user_a), 8000_i128);
+    let escrow = client.get_escrow(&id);
+    assert_eq!(escrow.state, EscrowState::Cancelled);
+    assert_eq!(escrow.locked_amount, 0);
+    assert_eq!(escrow.released_amount, 2000_i128);
+
+    // A stream left to run is fully paid out once vested
+    let now = env.ledger().timestamp();
+    options.kind = EscrowKind::Stream(Vesting { start: now, cliff: now, end: now + 10 * 86400 });
+    let id = client.deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &TEST_DEADLINE_DAYS, &options);
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+    env.ledger().with_mut(|li| {
+        li.timestamp = now + 11 * 86400;
+    });
+    assert_eq!(client.withdraw_vested(&user_b, &id), 3000_i128);
+    assert_eq!(client.get_escrow(&id).state, EscrowState::Released);
+
+    let lump_sum = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    let result = client.try_withdraw_vested(&user_b, &lump_sum);
+    assert_eq!(result, Err(Ok(Error::NotStream)));
+    let result = client.try_stop_stream(&user_a, &lump_sum);
+    assert_eq!(result, Err(Ok(Error::NotStream)));
+}
+
+#[test]
+fn test_batch_calls() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let user_c = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let entry = |freelancer: &Address, amount: i128, deadline_days: u64| BatchDeposit {
+        freelancer: freelancer.clone(),
+        arbiter: arbiter.clone(),
+        amount,
+        deadline_days,
+    };
+
+    let result = client.try_deposit_batch(&user_a, &token_contract, &Vec::new(&env));
+    assert_eq!(result, Err(Ok(Error::InvalidBatch)));
+
+    // One bad entry fails the whole batch
+    let entries = vec![&env, entry(&user_b, 100, TEST_DEADLINE_DAYS), entry(&user_c, 0, TEST_DEADLINE_DAYS)];
+    let result = client.try_deposit_batch(&user_a, &token_contract, &entries);
+    assert_eq!(result, Err(Ok(Error::AmountMustBePositive)));
+    assert_eq!(token.balance(&user_a), 1000_i128);
+    assert_eq!(client.get_user_escrows(&user_a).len(), 0);
+
+    let entries = vec![
+        &env,
+        entry(&user_b, 100, TEST_DEADLINE_DAYS),
+        entry(&user_c, 200, TEST_DEADLINE_DAYS),
+        entry(&user_b, 300, 1),
+    ];
+    let ids = client.deposit_batch(&user_a, &token_contract, &entries);
+    assert_eq!(ids, vec![&env, 0_u64, 1, 2]);
+    assert_eq!(token.balance(&user_a), 400_i128);
+    assert_eq!(client.get_escrow(&1).freelancer, user_c);
+    assert_eq!(client.get_escrow(&2).amount, 300_i128);
+
+    client.accept(&user_b, &0, &client.get_escrow(&0).terms_hash);
+    client.accept(&user_c, &1, &client.get_escrow(&1).terms_hash);
+
+    // The client can't approve an escrow the freelancer hasn't accepted,
+    // so nothing in the batch is approved
+    let result = client.try_approve_batch(&user_a, &vec![&env, 0_u64, 2]);
+    assert_eq!(result, Err(Ok(Error::NotAccepted)));
+    assert!(!client.get_escrow(&0).milestones.get(0).unwrap().approved_by_client);
+
+    client.approve_batch(&user_a, &vec![&env, 0_u64, 1]);
+    client.approve(&user_b, &0);
+    client.approve(&user_c, &1);
+    assert_eq!(token.balance(&user_b), 100_i128);
+    assert_eq!(token.balance(&user_c), 200_i128);
+
+    // Escrow 0 is already released, so the batch fails as a whole
+    env.ledger().with_mut(|li| {
+        li.timestamp += 172800;
+    });
+    let result = client.try_claim_timeout_batch(&user_a, &vec![&env, 2_u64, 0]);
+    assert_eq!(result, Err(Ok(Error::NotClaimable)));
+    assert_eq!(client.get_escrow(&2).state, EscrowState::Funded);
+
+    client.claim_timeout_batch(&user_a, &vec![&env, 2_u64]);
+    assert_eq!(token.balance(&user_a), 700_i128);
+    assert_eq!(client.get_escrow(&2).state, EscrowState::Cancelled);
+}
+
+#[test]
+fn test_keeper_bounty() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token = token::Client::new(&env, &token_contract);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+    let keeper = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let mut options = EscrowOptions {
+        terms_hash: BytesN::from_array(&env, &[0; 32]),
+        terms_uri: None,
+        panel: vec![&env, arbiter.clone()],
+        threshold: 1,
+        fallback: Fallback::Release,
+        kind: EscrowKind::LumpSum,
+        keeper_bounty: -1,
+    };
+    let amounts = vec![&env, 100_i128];
+
+    let result = client.try_deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &1, &options);
+    assert_eq!(result, Err(Ok(Error::AmountMustBePositive)));
+
+    // The bounty is collected on top of the escrowed amount
+    options.keeper_bounty = 10;
+    let expired = client.deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &1, &options);
+    let disputed = client.deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &1, &options);
+    let released = client.deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &1, &options);
+    assert_eq!(token.balance(&user_a), 670_i128);
+    assert_eq!(client.get_escrow(&expired).locked_amount, 100_i128);
+
+    // Closing normally returns the bounty to the client
+    client.accept(&user_b, &released, &client.get_escrow(&released).terms_hash);
+    client.approve(&user_a, &released);
+    client.approve(&user_b, &released);
+    assert_eq!(token.balance(&user_a), 680_i128);
+    assert_eq!(client.get_escrow(&released).keeper_bounty, 0);
+
+    client.dispute(&user_b, &disputed);
+
+    // Whoever settles an expired escrow collects its bounty
+    env.ledger().with_mut(|li| {
+        li.timestamp += 15 * 86400;
+    });
+    client.claim_timeout_batch(&keeper, &vec![&env, expired, disputed]);
+    assert_eq!(token.balance(&keeper), 20_i128);
+    assert_eq!(token.balance(&user_a), 780_i128);
+    assert_eq!(token.balance(&user_b), 200_i128);
+    assert_eq!(client.get_escrow(&disputed).state, EscrowState::Resolved);
+    assert_eq!(token.balance(&contract_id), 0_i128);
+
+    // While paused only client refunds go out: the bounty goes back too
+    let paused = client.deposit_with_options(&user_a, &user_b, &token_contract, &amounts, &1, &options);
+    client.pause();
+    env.ledger().with_mut(|li| {
+        li.timestamp += 86400 + PAUSE_GRACE_PERIOD;
+    });
+    client.claim_timeout(&keeper, &paused);
+    assert_eq!(token.balance(&keeper), 20_i128);
+    assert_eq!(token.balance(&user_a), 780_i128);
+    assert_eq!(token.balance(&contract_id), 0_i128);
+}
+
+#[test]
+fn test_expirable_index() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let late = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &TEST_DEADLINE_DAYS);
+    let soon = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1);
+    let disputed = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1);
+    let cancelled = client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &1);
+
+    let now = env.ledger().timestamp();
+    assert_eq!(client.get_expirable(&(now + 86400), &10).len(), 0);
+
+    // Closed escrows leave the index, disputed ones move to their arbitration window
+    client.cancel(&user_a, &cancelled);
+    client.dispute(&user_a, &disputed);
+
+    let expirable = client.get_expirable(&(now + 2 * 86400), &10);
+    assert_eq!(expirable.len(), 1);
+    assert_eq!(expirable.get(0).unwrap().id, soon);
+
+    let expirable = client.get_expirable(&(now + 20 * 86400), &10);
+    assert_eq!(expirable.len(), 2);
+    assert_eq!(expirable.get(0).unwrap().id, soon);
+    assert_eq!(expirable.get(1).unwrap().id, disputed);
+    assert_eq!(client.get_expirable(&(now + 20 * 86400), &1).len(), 1);
+
+    let expirable = client.get_expirable(&(now + 40 * 86400), &10);
+    assert_eq!(expirable.len(), 3);
+    assert_eq!(expirable.get(2).unwrap().id, late);
+
+    env.ledger().with_mut(|li| {
+        li.timestamp += 2 * 86400;
+    });
+    client.claim_timeout(&user_a, &soon);
+    client.resolve(&arbiter, &disputed, &user_a);
+
+    let expirable = client.get_expirable(&(now + 40 * 86400), &10);
+    assert_eq!(expirable.len(), 1);
+    assert_eq!(expirable.get(0).unwrap().id, late);
+}
+
+// Last event the contract itself published during the latest call
+fn last_event(env: &Env, contract_id: &Address) -> (Symbol, Val) {
+    let (_, topics, data) = env
+        .events()
+        .all()
+        .iter()
+        .filter(|(id, _, _)| id == contract_id)
+        .last()
+        .unwrap();
+    (Symbol::from_val(env, &topics.get(0).unwrap()), data)
+}
+
+#[test]
+fn test_event_schema() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let amounts = vec![&env, 100_i128, 200_i128];
+    let id = client.deposit_milestones(&user_a, &user_b, &arbiter, &token_contract, &amounts, &TEST_DEADLINE_DAYS);
+    let deadline = client.get_escrow(&id).deadline;
+
+    let (topic, data) = last_event(&env, &contract_id);
+    assert_eq!(topic, symbol_short!("deposit"));
+    let event = DepositEvent::from_val(&env, &data);
+    let header = EventHeader {
+        schema: 1,
+        escrow_id: id,
+        state: EscrowState::Funded,
+        deadline,
+        token: token_contract.clone(),
+        locked_amount: 300,
+        released_amount: 0,
+    };
+    assert_eq!(event.header, header);
+    assert_eq!(event.amount, 300);
+
+    client.accept(&user_b, &id, &client.get_escrow(&id).terms_hash);
+    let (topic, data) = last_event(&env, &contract_id);
+    assert_eq!(topic, symbol_short!("accept"));
+    assert_eq!(AcceptEvent::from_val(&env, &data).header.state, EscrowState::Accepted);
+
+    // A single approval is recorded even though nothing is released
+    client.approve(&user_a, &id);
+    let (topic, data) = last_event(&env, &contract_id);
+    assert_eq!(topic, symbol_short!("approve"));
+    let event = ApproveEvent::from_val(&env, &data);
+    assert_eq!(event.approver, user_a);
+    assert_eq!(event.milestone, 0);
+
+    client.approve(&user_b, &id);
+    let (topic, data) = last_event(&env, &contract_id);
+    assert_eq!(topic, symbol_short!("release"));
+    let event = ReleaseEvent::from_val(&env, &data);
+    assert_eq!(event.milestone, 0);
+    assert_eq!(event.paid, 100);
+    assert_eq!(event.header.locked_amount, 200);
+    assert_eq!(event.header.released_amount, 100);
+
+    client.dispute(&user_b, &id);
+    let (topic, data) = last_event(&env, &contract_id);
+    assert_eq!(topic, symbol_short!("dispute"));
+    let event = DisputeEvent::from_val(&env, &data);
+    assert_eq!(event.header.state, EscrowState::Disputed);
+    assert_eq!(event.arbitration_deadline, client.get_escrow(&id).arbitration_deadline);
+
+    client.resolve(&arbiter, &id, &user_b);
+    let (topic, data) = last_event(&env, &contract_id);
+    assert_eq!(topic, symbol_short!("resolve"));
+    let event = PayoutEvent::from_val(&env, &data);
+    assert_eq!(event.header.state, EscrowState::Resolved);
+    assert_eq!(event.header.locked_amount, 0);
+    assert_eq!(event.arbiter, Some(arbiter));
+    assert_eq!(event.freelancer_paid, 200);
+    assert_eq!(event.client_amount, 0);
 }
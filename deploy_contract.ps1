@@ -29,14 +29,25 @@ try {
     Write-Host "Funding might have failed or already funded. Continuing..."
 }
 
-# Build Contract
+# Build Contract (only the deployer baked in here can initialize it)
 Write-Host "Building Smart Contract..."
+$env:SAFE_HANDS_DEPLOYER = soroban config identity address deployer
 cargo build --target wasm32-unknown-unknown --release
 
 # Deploy
 Write-Host "Deploying to Stellar Testnet..."
 $contractId = soroban contract deploy --wasm target/wasm32-unknown-unknown/release/safe_hands_contract.wasm --source deployer --network testnet
 
+# Initialize (deployer becomes admin)
+Write-Host "Initializing contract with deployer as admin..."
+$adminAddress = soroban config identity address deployer
+soroban contract invoke --id $contractId --source deployer --network testnet -- initialize --admin $adminAddress
+
+# Accept native XLM (SAC) for escrows
+Write-Host "Allowing native XLM token..."
+$nativeToken = soroban contract id asset --asset native --network testnet
+soroban contract invoke --id $contractId --source deployer --network testnet -- allow_token --token $nativeToken
+
 Write-Host "Deployment Complete!" -ForegroundColor Green
 Write-Host "Contract ID: $contractId" -ForegroundColor Green
 Write-Host "Please copy this ID and update frontend/utils/soroban.ts"
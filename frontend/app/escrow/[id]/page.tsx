@@ -9,19 +9,25 @@ import { ArrowLeft, CheckCircle, ShieldCheck, AlertTriangle, XCircle, Scale, Ban
 import Link from "next/link";
 import toast from "react-hot-toast";
 
-type EscrowState = "Funded" | "Released" | "Cancelled" | "Disputed" | "Resolved";
+type EscrowState = "Funded" | "Accepted" | "Released" | "Cancelled" | "Disputed" | "Resolved" | "Settled";
 
 const STATE_CONFIG: Record<EscrowState, { bg: string; icon: React.ReactNode; label: string; description: string }> = {
-    Funded: { bg: "bg-yellow-400", icon: <AlertTriangle size={20} />, label: "Active", description: "Funds are locked in the smart contract. Awaiting mutual approval to release." },
+    Funded: { bg: "bg-yellow-400", icon: <AlertTriangle size={20} />, label: "Offered", description: "Funds are locked in the smart contract. Awaiting the freelancer's acceptance." },
+    Accepted: { bg: "bg-yellow-400", icon: <AlertTriangle size={20} />, label: "In Progress", description: "The freelancer accepted the job. Awaiting mutual approval to release." },
     Released: { bg: "bg-green-400", icon: <CheckCircle size={20} />, label: "Completed", description: "Both parties approved. Funds have been transferred to the freelancer." },
     Cancelled: { bg: "bg-red-300", icon: <Ban size={20} />, label: "Cancelled", description: "Escrow cancelled. Funds refunded to the client." },
     Disputed: { bg: "bg-orange-400", icon: <Scale size={20} />, label: "In Dispute", description: "A dispute has been raised. The arbiter will review and decide." },
     Resolved: { bg: "bg-blue-300", icon: <ShieldCheck size={20} />, label: "Resolved", description: "Dispute resolved by arbiter. Funds awarded to the winner." },
+    Settled: { bg: "bg-blue-300", icon: <CheckCircle size={20} />, label: "Settled", description: "Dispute settled by agreement between the parties." },
 };
 
 function getGuidance(state: EscrowState, isClient: boolean, isFreelancer: boolean, isArbiter: boolean, approvedByClient: boolean, approvedByFreelancer: boolean): string | null {
     if (state === "Funded") {
-        if (isClient && !approvedByClient && !approvedByFreelancer) return "⏳ Waiting for freelancer to complete the work. You can cancel if needed.";
+        if (isClient) return "⏳ Waiting for the freelancer to accept the job. You can cancel until then.";
+        if (isFreelancer) return "👉 Review the job requirements below and accept the job from your dashboard.";
+    }
+    if (state === "Accepted") {
+        if (isClient && !approvedByClient && !approvedByFreelancer) return "⏳ Waiting for freelancer to complete the work.";
         if (isClient && !approvedByClient && approvedByFreelancer) return "👉 Freelancer approved delivery. Review and click Approve to release payment, or Dispute if unsatisfied.";
         if (isClient && approvedByClient && !approvedByFreelancer) return "✅ You approved. Waiting for freelancer to also approve for fund release.";
         if (isFreelancer && !approvedByFreelancer) return "👉 Review the job requirements below. Click Approve when you've delivered the work.";
@@ -93,7 +99,7 @@ export default function EscrowDetailPage({ params }: { params: Promise<{ id: str
             const data = await getEscrow(escrowId, userAddress || undefined);
             if (data) {
                 const d = data as Record<string, unknown>;
-                const states: EscrowState[] = ["Funded", "Released", "Cancelled", "Disputed", "Resolved"];
+                const states: EscrowState[] = ["Funded", "Released", "Cancelled", "Disputed", "Resolved", "Accepted", "Settled"];
                 setEscrow({
                     client: d.client?.toString() ?? "",
                     freelancer: d.freelancer?.toString() ?? "",
@@ -160,14 +166,14 @@ export default function EscrowDetailPage({ params }: { params: Promise<{ id: str
     const isFreelancer = userAddress === escrow.freelancer;
     const isArbiter = userAddress === escrow.arbiter;
 
-    const canApprove = escrow.state === "Funded" && ((isClient && !escrow.approved_by_client) || (isFreelancer && !escrow.approved_by_freelancer));
-    const canCancel = escrow.state === "Funded" && isClient && !escrow.approved_by_freelancer;
-    const canDispute = escrow.state === "Funded" && (isClient || isFreelancer);
+    const canApprove = escrow.state === "Accepted" && ((isClient && !escrow.approved_by_client) || (isFreelancer && !escrow.approved_by_freelancer));
+    const canCancel = escrow.state === "Funded" && isClient;
+    const canDispute = (escrow.state === "Funded" || escrow.state === "Accepted") && (isClient || isFreelancer);
     const canResolve = escrow.state === "Disputed" && isArbiter;
 
     const deadlineTs = escrow.deadline ? Number(escrow.deadline) : 0;
     const isExpired = deadlineTs > 0 && Date.now() / 1000 > deadlineTs;
-    const canClaimTimeout = isExpired && (escrow.state === "Funded" || escrow.state === "Disputed");
+    const canClaimTimeout = isExpired && (escrow.state === "Funded" || escrow.state === "Accepted" || escrow.state === "Disputed");
     const deadlineDate = deadlineTs > 0 ? new Date(deadlineTs * 1000) : null;
 
     const guidance = getGuidance(escrow.state, isClient, isFreelancer, isArbiter, escrow.approved_by_client, escrow.approved_by_freelancer);
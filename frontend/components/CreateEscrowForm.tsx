@@ -4,7 +4,7 @@ import { useState } from "react";
 import { Loader2, Plus } from "lucide-react";
 import { deposit } from "@/utils/soroban";
 import { parseXLMtoStroops } from "@/utils/format";
-import { saveJobMetadata, JOB_CATEGORIES, JobCategory } from "@/utils/jobStore";
+import { saveJobMetadata, hashJobTerms, JOB_CATEGORIES, JobCategory } from "@/utils/jobStore";
 import toast from "react-hot-toast";
 
 export default function CreateEscrowForm({
@@ -32,14 +32,16 @@ export default function CreateEscrowForm({
         try {
             const arbiterAddr = arbiter || clientAddress;
             const stroops = parseXLMtoStroops(amount);
-            const result = await deposit(clientAddress, freelancer, arbiterAddr, stroops, parseInt(deadlineDays) || 30);
+            const terms = { title: title.trim(), description: description.trim(), category };
+            const termsHash = await hashJobTerms(terms);
+            const result = await deposit(clientAddress, freelancer, arbiterAddr, stroops, parseInt(deadlineDays) || 30, termsHash);
 
             // Extract escrow ID from transaction result and save job metadata
             // The escrow ID is typically the return value from the deposit contract call
             // For now, we save with the current next-ID approach
             const escrowId = extractEscrowId(result);
             if (escrowId !== null) {
-                saveJobMetadata(escrowId.toString(), { title: title.trim(), description: description.trim(), category });
+                saveJobMetadata(escrowId.toString(), terms);
             }
 
             toast.success("Job escrow created successfully! 🎉");
@@ -1,14 +1,14 @@
 "use client";
 
 import { CheckCircle, ShieldCheck, AlertTriangle, XCircle, Scale, Ban, ExternalLink, Clock, Info } from "lucide-react";
-import { approve, cancel, dispute, resolve, claimTimeout } from "@/utils/soroban";
+import { acceptJob, approve, cancel, decline, dispute, resolve, claimTimeout, offerSettlement, acceptSettlement } from "@/utils/soroban";
 import { formatXLM } from "@/utils/format";
 import { getJobMetadata, CATEGORY_CONFIG, JobMetadata } from "@/utils/jobStore";
 import { useState, useEffect } from "react";
 import toast from "react-hot-toast";
 import Link from "next/link";
 
-export type EscrowState = "Funded" | "Released" | "Cancelled" | "Disputed" | "Resolved";
+export type EscrowState = "Funded" | "Accepted" | "Released" | "Cancelled" | "Disputed" | "Resolved" | "Settled";
 
 export interface EscrowProps {
     id: string;
@@ -20,25 +20,32 @@ export interface EscrowProps {
     approvedByClient: boolean;
     approvedByFreelancer: boolean;
     deadline?: string;
+    termsHash?: Uint8Array;
     userAddress: string;
     onUpdate: () => void;
 }
 
 const STATE_CONFIG: Record<EscrowState, { bg: string; icon: React.ReactNode; label: string }> = {
-    Funded: { bg: "bg-yellow-400", icon: <AlertTriangle size={16} />, label: "Active" },
+    Funded: { bg: "bg-yellow-400", icon: <AlertTriangle size={16} />, label: "Offered" },
+    Accepted: { bg: "bg-yellow-400", icon: <AlertTriangle size={16} />, label: "In Progress" },
     Released: { bg: "bg-green-400", icon: <CheckCircle size={16} />, label: "Completed" },
     Cancelled: { bg: "bg-red-300", icon: <Ban size={16} />, label: "Cancelled" },
     Disputed: { bg: "bg-orange-400", icon: <Scale size={16} />, label: "In Dispute" },
     Resolved: { bg: "bg-blue-300", icon: <ShieldCheck size={16} />, label: "Resolved" },
+    Settled: { bg: "bg-blue-300", icon: <CheckCircle size={16} />, label: "Settled" },
 };
 
 /** Step-by-step guidance messages per state and role */
 function getGuidance(state: EscrowState, isClient: boolean, isFreelancer: boolean, isArbiter: boolean, approvedByClient: boolean, approvedByFreelancer: boolean): string | null {
     if (state === "Funded") {
-        if (isClient && !approvedByClient && !approvedByFreelancer) return "⏳ Waiting for freelancer to start. You can cancel if needed.";
-        if (isClient && !approvedByClient && approvedByFreelancer) return "👉 Freelancer approved. Review the work and click Approve to release payment.";
+        if (isClient) return "⏳ Waiting for freelancer to accept the job. You can cancel until then.";
+        if (isFreelancer) return "👉 Review the job details and click Accept to take the job.";
+    }
+    if (state === "Accepted") {
+        if (isClient && !approvedByClient && !approvedByFreelancer) return "⏳ Freelancer is working on the job.";
+        if (isClient && !approvedByClient && approvedByFreelancer) return "👉 Freelancer delivered. Review the work and click Approve to release payment.";
         if (isClient && approvedByClient && !approvedByFreelancer) return "✅ You approved. Waiting for freelancer to approve and release funds.";
-        if (isFreelancer && !approvedByFreelancer) return "👉 Review the job details. Click Approve when you've completed the work.";
+        if (isFreelancer && !approvedByFreelancer) return "👉 Click Approve when you've completed the work.";
         if (isFreelancer && approvedByFreelancer) return "✅ You approved. Waiting for client to approve and release your payment.";
     }
     if (state === "Disputed") {
@@ -48,6 +55,7 @@ function getGuidance(state: EscrowState, isClient: boolean, isFreelancer: boolea
     if (state === "Released") return "🎉 Job completed! Funds have been released to the freelancer.";
     if (state === "Cancelled") return "❌ This job was cancelled. Funds refunded to the client.";
     if (state === "Resolved") return "⚖️ Dispute resolved by arbiter. Funds awarded to the winner.";
+    if (state === "Settled") return "🤝 Dispute settled by agreement between client and freelancer.";
     return null;
 }
 
@@ -66,11 +74,13 @@ export default function EscrowCard({
     approvedByClient,
     approvedByFreelancer,
     deadline,
+    termsHash,
     userAddress,
     onUpdate,
 }: EscrowProps) {
     const [loading, setLoading] = useState(false);
     const [resolveWinner, setResolveWinner] = useState("");
+    const [settlementPercent, setSettlementPercent] = useState("50");
     const [jobMeta, setJobMeta] = useState<JobMetadata | null>(null);
 
     const isClient = userAddress === client;
@@ -81,23 +91,32 @@ export default function EscrowCard({
         setJobMeta(getJobMetadata(id));
     }, [id]);
 
+    const canAccept =
+        state === "Funded" && isFreelancer;
+
     const canApprove =
-        state === "Funded" &&
+        state === "Accepted" &&
         ((isClient && !approvedByClient) || (isFreelancer && !approvedByFreelancer));
 
     const canCancel =
-        state === "Funded" && isClient && !approvedByFreelancer;
+        state === "Funded" && isClient;
+
+    const canDecline =
+        (state === "Funded" || state === "Accepted" || state === "Disputed") && isFreelancer;
 
     const canDispute =
-        state === "Funded" && (isClient || isFreelancer);
+        (state === "Funded" || state === "Accepted") && (isClient || isFreelancer);
 
     const canResolve =
         state === "Disputed" && isArbiter;
 
+    const canSettle =
+        state === "Disputed" && (isClient || isFreelancer);
+
     // Deadline logic
     const deadlineTs = deadline ? Number(deadline) : 0;
     const isExpired = deadlineTs > 0 && Date.now() / 1000 > deadlineTs;
-    const canClaimTimeout = isExpired && (state === "Funded" || state === "Disputed");
+    const canClaimTimeout = isExpired && (state === "Funded" || state === "Accepted" || state === "Disputed");
     const deadlineDate = deadlineTs > 0 ? new Date(deadlineTs * 1000) : null;
 
     const stateConfig = STATE_CONFIG[state] || STATE_CONFIG.Funded;
@@ -151,7 +170,7 @@ export default function EscrowCard({
                     <div className="text-xs font-bold uppercase tracking-widest text-gray-500">
                         {state === "Released" ? "Released" : state === "Cancelled" ? "Refunded" : "Locked"}
                     </div>
-                    {deadlineDate && state === "Funded" && (
+                    {deadlineDate && (state === "Funded" || state === "Accepted") && (
                         <div className={`mt-1 text-xs font-mono flex items-center gap-1 sm:justify-end ${isExpired ? 'text-red-600' : 'text-gray-500'}`}>
                             <Clock size={10} />
                             {isExpired ? "EXPIRED" : `Due: ${deadlineDate.toLocaleDateString()}`}
@@ -201,6 +220,16 @@ export default function EscrowCard({
 
             {/* Actions */}
             <div className="space-y-3">
+                {canAccept && (
+                    <button
+                        onClick={() => termsHash && handleAction(() => acceptJob(userAddress, id, termsHash), "Job accepted! 🤝")}
+                        disabled={loading || !termsHash}
+                        className="w-full bg-blue-600 text-white py-3 font-black uppercase tracking-widest hover:bg-blue-700 disabled:bg-gray-400 border-2 border-black brutalist-shadow active:shadow-none active:translate-x-[4px] active:translate-y-[4px] transition-all flex items-center justify-center gap-2 text-sm"
+                    >
+                        {loading ? "Processing..." : <><CheckCircle size={18} /> Accept Job</>}
+                    </button>
+                )}
+
                 {canApprove && (
                     <button
                         onClick={() => handleAction(() => approve(userAddress, id), "Approved successfully! ✅")}
@@ -221,6 +250,16 @@ export default function EscrowCard({
                     </button>
                 )}
 
+                {canDecline && (
+                    <button
+                        onClick={() => handleAction(() => decline(userAddress, id), "Job declined and client refunded")}
+                        disabled={loading}
+                        className="w-full bg-red-500 text-white py-3 font-black uppercase tracking-widest hover:bg-red-600 disabled:bg-gray-400 border-2 border-black brutalist-shadow active:shadow-none active:translate-x-[4px] active:translate-y-[4px] transition-all flex items-center justify-center gap-2 text-sm"
+                    >
+                        {loading ? "Processing..." : <><XCircle size={18} /> Decline & Refund Client</>}
+                    </button>
+                )}
+
                 {canDispute && (
                     <button
                         onClick={() => handleAction(() => dispute(userAddress, id), "Dispute raised! ⚠️")}
@@ -231,6 +270,37 @@ export default function EscrowCard({
                     </button>
                 )}
 
+                {canSettle && (
+                    <div className="border-2 border-black p-4 bg-blue-50">
+                        <h4 className="font-black uppercase text-sm mb-3">Settle Without Arbiter</h4>
+                        <div className="flex flex-col sm:flex-row gap-2">
+                            <input
+                                type="number"
+                                min="0"
+                                max="100"
+                                placeholder="Freelancer share (%)"
+                                value={settlementPercent}
+                                onChange={(e) => setSettlementPercent(e.target.value)}
+                                className="flex-1 border-2 border-black p-2 font-mono text-sm"
+                            />
+                            <button
+                                onClick={() => handleAction(() => offerSettlement(userAddress, id, Math.round(Number(settlementPercent) * 100)), "Settlement offered! 🤝")}
+                                disabled={loading || settlementPercent === ""}
+                                className="bg-blue-600 text-white px-4 py-2 font-bold uppercase border-2 border-black hover:bg-blue-700 disabled:bg-gray-400"
+                            >
+                                {loading ? "..." : "Offer"}
+                            </button>
+                            <button
+                                onClick={() => handleAction(() => acceptSettlement(userAddress, id), "Settlement accepted! 🤝")}
+                                disabled={loading}
+                                className="bg-green-500 text-white px-4 py-2 font-bold uppercase border-2 border-black hover:bg-green-600 disabled:bg-gray-400"
+                            >
+                                {loading ? "..." : "Accept Offer"}
+                            </button>
+                        </div>
+                    </div>
+                )}
+
                 {canResolve && (
                     <div className="border-2 border-black p-4 bg-blue-50">
                         <h4 className="font-black uppercase text-sm mb-3">Resolve Dispute</h4>
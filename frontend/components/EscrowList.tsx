@@ -7,9 +7,9 @@ import { Loader2, Filter } from "lucide-react";
 type FilterOption = "All" | "Active" | "Completed" | "Disputed";
 
 const FILTER_CONFIG: Record<FilterOption, { states: EscrowState[]; bg: string }> = {
-    All: { states: ["Funded", "Released", "Cancelled", "Disputed", "Resolved"], bg: "bg-black text-white" },
-    Active: { states: ["Funded"], bg: "bg-yellow-400" },
-    Completed: { states: ["Released", "Cancelled", "Resolved"], bg: "bg-green-400" },
+    All: { states: ["Funded", "Accepted", "Released", "Cancelled", "Disputed", "Resolved", "Settled"], bg: "bg-black text-white" },
+    Active: { states: ["Funded", "Accepted"], bg: "bg-yellow-400" },
+    Completed: { states: ["Released", "Cancelled", "Resolved", "Settled"], bg: "bg-green-400" },
     Disputed: { states: ["Disputed"], bg: "bg-orange-400" },
 };
 
@@ -33,27 +33,15 @@ export default function EscrowList({ userAddress, refreshKey = 0 }: EscrowListPr
         const fetchEscrows = async () => {
             setLoading(true);
             try {
-                const { getUserEscrows, getEscrow } = await import("@/utils/soroban");
-                const ids = await getUserEscrows(userAddress);
-                console.log("User Escrow IDs:", ids);
-
-                if (!ids || ids.length === 0) {
-                    if (isMounted) {
-                        setEscrows([]);
-                        setLoading(false);
-                    }
-                    return;
-                }
-
-                const promises = (ids as Array<string | number>).map(async (id) => {
-                    const details = await getEscrow(id.toString(), userAddress);
-                    if (!details) return null;
+                const { getUserEscrowsPage } = await import("@/utils/soroban");
+                const entries = await getUserEscrowsPage(userAddress);
+                console.log("User Escrow IDs:", entries.map(e => e.id));
 
-                    const escrowData = details as Record<string, unknown>;
-                    const getField = (key: string) => escrowData[key];
+                const results = entries.map(({ id, escrow }) => {
+                    const getField = (key: string) => escrow[key];
 
                     const stateEnum = getField("state");
-                    const states = ["Funded", "Released", "Cancelled", "Disputed", "Resolved"];
+                    const states = ["Funded", "Released", "Cancelled", "Disputed", "Resolved", "Accepted", "Settled"];
                     const stateStr = states[Number(stateEnum)] || "Unknown";
 
                     return {
@@ -66,12 +54,12 @@ export default function EscrowList({ userAddress, refreshKey = 0 }: EscrowListPr
                         approvedByClient: Boolean(getField("approved_by_client")),
                         approvedByFreelancer: Boolean(getField("approved_by_freelancer")),
                         deadline: getField("deadline")?.toString(),
+                        termsHash: getField("terms_hash") as Uint8Array | undefined,
                     };
                 });
 
-                const results = await Promise.all(promises);
                 if (isMounted) {
-                    const sorted = results.filter((r): r is NonNullable<typeof r> => r !== null).sort((a, b) => Number(b.id) - Number(a.id));
+                    const sorted = results.sort((a, b) => Number(b.id) - Number(a.id));
                     setEscrows(sorted);
                 }
 
@@ -6,6 +6,9 @@
  * off-chain in the browser's localStorage.
  * 
  * Key format: "safehands_job_{escrowId}"
+ *
+ * The SHA-256 of the terms (see `hashJobTerms`) is anchored on the escrow
+ * at deposit, so the freelancer and arbiter can check a copy against it.
  */
 
 export interface JobMetadata {
@@ -34,6 +37,17 @@ export const JOB_CATEGORIES: JobCategory[] = [
 
 const STORAGE_PREFIX = "safehands_job_";
 
+/** SHA-256 of the job terms, in a fixed field order so anyone can recompute it. */
+export async function hashJobTerms(metadata: Omit<JobMetadata, "createdAt">): Promise<Uint8Array> {
+    const canonical = JSON.stringify({
+        title: metadata.title,
+        description: metadata.description,
+        category: metadata.category,
+    });
+    const digest = await crypto.subtle.digest("SHA-256", new TextEncoder().encode(canonical));
+    return new Uint8Array(digest);
+}
+
 export function saveJobMetadata(escrowId: string, metadata: Omit<JobMetadata, "createdAt">): void {
     const data: JobMetadata = {
         ...metadata,
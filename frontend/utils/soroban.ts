@@ -23,37 +23,153 @@ export const NETWORK_PASSPHRASE = Networks.TESTNET;
 export const server = new rpc.Server(RPC_URL);
 
 // EscrowState enum mapping:
-// 0: Funded, 1: Released, 2: Cancelled, 3: Disputed, 4: Resolved
+// 0: Funded, 1: Released, 2: Cancelled, 3: Disputed, 4: Resolved, 5: Accepted, 6: Settled
+
+// Contract error codes (mirrors `Error` in contracts/src/lib.rs)
+export const CONTRACT_ERRORS: Record<number, string> = {
+  1: "Amount must be positive",
+  2: "Deadline must be at least 1 day",
+  3: "At least one milestone is required",
+  4: "Amount overflow",
+  5: "Escrow ID limit reached",
+  6: "Escrow does not exist",
+  7: "Escrow not in a state to be approved",
+  8: "Escrow deadline has passed. Use claim_timeout instead.",
+  9: "No milestone left to approve",
+  10: "Not authorized to approve",
+  11: "Only client can cancel",
+  12: "Escrow cannot be cancelled in current state",
+  13: "Cannot cancel: Freelancer has already accepted the job",
+  14: "Only parties can raise dispute",
+  15: "Can only dispute active/funded escrows",
+  16: "Not the authorized arbiter",
+  17: "Escrow not in a state to be resolved",
+  18: "Winner must be one of the parties",
+  19: "Escrow not in a claimable state",
+  20: "Deadline has not passed yet",
+  21: "Split must add up to the locked amount",
+  22: "Fee rate out of range",
+  23: "Contract already initialized",
+  24: "Contract not initialized",
+  25: "No pending admin transfer",
+  26: "Storage migration pending",
+  27: "Contract is paused",
+  28: "Token is not accepted for escrows",
+  29: "Amount is below the minimum for this token",
+  30: "Amount is above the maximum for this token",
+  31: "Invalid token limits",
+  32: "Invalid arbiter panel or threshold",
+  33: "This escrow is decided by panel vote",
+  34: "Escrow is not disputed",
+  35: "Evidence limit reached",
+  36: "Invalid evidence hash",
+  37: "Only the client or freelancer can do this",
+  38: "The arbiter still has time to decide this dispute",
+  39: "No pending amendment",
+  40: "This amendment can't be applied",
+  41: "Terms don't match the ones anchored at deposit",
+  42: "Invalid terms URI",
+  43: "The freelancer hasn't accepted this job yet",
+  44: "Only the freelancer can do this",
+  45: "No settlement offer to accept",
+  46: "This settlement offer can't be accepted",
+  47: "Invalid vesting schedule",
+  48: "This escrow is not a stream",
+  49: "Nothing has vested since the last withdrawal",
+  50: "Batch must contain between 1 and 25 entries",
+};
+
+// Extracts the contract error code from a host error such as
+// "HostError: Error(Contract, #13)". Returns null for non-contract errors.
+export function getContractErrorCode(err: unknown): number | null {
+  const message = err instanceof Error ? err.message : String(err);
+  const match = message.match(/Error\(Contract, #(\d+)\)/);
+  return match ? Number(match[1]) : null;
+}
 
+// Deposits through `deposit_with_options` so the job terms hash (and an
+// optional URI for the terms document) is anchored on-chain.
 export async function deposit(
   clientAddress: string,
   freelancerAddress: string,
   arbiterAddress: string,
   amount: string,
-  deadlineDays: number = 30
+  deadlineDays: number,
+  termsHash: Uint8Array,
+  termsUri?: string
 ) {
   const contract = new Contract(CONTRACT_ID);
   // Derive Native Token ID dynamically (Wrapped XLM for Soroban)
   const root = Asset.native();
   const NATIVE_TOKEN = root.contractId(NETWORK_PASSPHRASE);
 
+  // EscrowOptions struct: map entries must be sorted by field name
+  const options = xdr.ScVal.scvMap([
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol("fallback"),
+      val: xdr.ScVal.scvVec([xdr.ScVal.scvSymbol("Refund")]),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol("keeper_bounty"),
+      val: nativeToScVal(BigInt(0), { type: "i128" }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol("kind"),
+      val: xdr.ScVal.scvVec([xdr.ScVal.scvSymbol("LumpSum")]),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol("panel"),
+      val: xdr.ScVal.scvVec([new Address(arbiterAddress).toScVal()]),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol("terms_hash"),
+      val: xdr.ScVal.scvBytes(Buffer.from(termsHash)),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol("terms_uri"),
+      val: termsUri ? xdr.ScVal.scvBytes(Buffer.from(termsUri)) : xdr.ScVal.scvVoid(),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol("threshold"),
+      val: nativeToScVal(1, { type: "u32" }),
+    }),
+  ]);
+
   const args = [
     new Address(clientAddress).toScVal(),
     new Address(freelancerAddress).toScVal(),
-    new Address(arbiterAddress).toScVal(),
     new Address(NATIVE_TOKEN).toScVal(),
-    nativeToScVal(BigInt(amount), { type: "i128" }),
+    xdr.ScVal.scvVec([nativeToScVal(BigInt(amount), { type: "i128" })]),
     nativeToScVal(BigInt(deadlineDays), { type: "u64" }),
+    options,
   ];
 
-  const op = contract.call("deposit", ...args);
+  const op = contract.call("deposit_with_options", ...args);
 
   return await submitTx(clientAddress, op);
 }
 
+// Freelancer takes the job, committing to the terms hash anchored at deposit
+export async function acceptJob(
+  freelancerAddress: string,
+  escrowId: string,
+  termsHash: Uint8Array
+) {
+  const contract = new Contract(CONTRACT_ID);
+  const args = [
+    new Address(freelancerAddress).toScVal(),
+    nativeToScVal(BigInt(escrowId), { type: "u64" }),
+    xdr.ScVal.scvBytes(Buffer.from(termsHash)),
+  ];
+  const op = contract.call("accept", ...args);
+  return await submitTx(freelancerAddress, op);
+}
+
 export async function claimTimeout(escrowId: string, callerAddress: string) {
   const contract = new Contract(CONTRACT_ID);
+  // The caller collects the escrow's keeper bounty, if any
   const args = [
+    new Address(callerAddress).toScVal(),
     nativeToScVal(BigInt(escrowId), { type: "u64" }),
   ];
   const op = contract.call("claim_timeout", ...args);
@@ -92,6 +208,23 @@ export async function cancel(
   return await submitTx(clientAddress, op);
 }
 
+// Freelancer returns the locked funds to the client and closes the job
+export async function decline(
+  freelancerAddress: string,
+  escrowId: string
+) {
+  const contract = new Contract(CONTRACT_ID);
+
+  const args = [
+    new Address(freelancerAddress).toScVal(),
+    nativeToScVal(BigInt(escrowId), { type: "u64" }),
+  ];
+
+  const op = contract.call("decline", ...args);
+
+  return await submitTx(freelancerAddress, op);
+}
+
 export async function dispute(
   callerAddress: string,
   escrowId: string
@@ -126,6 +259,105 @@ export async function resolve(
   return await submitTx(arbiterAddress, op);
 }
 
+// Award the freelancer `freelancerBps` basis points of the locked amount,
+// the rest goes back to the client.
+export async function resolveSplit(
+  arbiterAddress: string,
+  escrowId: string,
+  freelancerBps: number
+) {
+  const contract = new Contract(CONTRACT_ID);
+
+  const split = xdr.ScVal.scvVec([
+    xdr.ScVal.scvSymbol("Bps"),
+    nativeToScVal(freelancerBps, { type: "u32" }),
+  ]);
+
+  const args = [
+    new Address(arbiterAddress).toScVal(),
+    nativeToScVal(BigInt(escrowId), { type: "u64" }),
+    split,
+  ];
+
+  const op = contract.call("resolve_split", ...args);
+
+  return await submitTx(arbiterAddress, op);
+}
+
+// Freelancer withdraws whatever has vested on a stream so far
+export async function withdrawVested(
+  freelancerAddress: string,
+  escrowId: string
+) {
+  const contract = new Contract(CONTRACT_ID);
+
+  const args = [
+    new Address(freelancerAddress).toScVal(),
+    nativeToScVal(BigInt(escrowId), { type: "u64" }),
+  ];
+
+  const op = contract.call("withdraw_vested", ...args);
+
+  return await submitTx(freelancerAddress, op);
+}
+
+// Client stops a stream; only the unvested part is refunded
+export async function stopStream(
+  clientAddress: string,
+  escrowId: string
+) {
+  const contract = new Contract(CONTRACT_ID);
+
+  const args = [
+    new Address(clientAddress).toScVal(),
+    nativeToScVal(BigInt(escrowId), { type: "u64" }),
+  ];
+
+  const op = contract.call("stop_stream", ...args);
+
+  return await submitTx(clientAddress, op);
+}
+
+// Offer the other party a split of a disputed escrow (freelancer share in bps)
+export async function offerSettlement(
+  partyAddress: string,
+  escrowId: string,
+  freelancerBps: number
+) {
+  const contract = new Contract(CONTRACT_ID);
+
+  const split = xdr.ScVal.scvVec([
+    xdr.ScVal.scvSymbol("Bps"),
+    nativeToScVal(freelancerBps, { type: "u32" }),
+  ]);
+
+  const args = [
+    new Address(partyAddress).toScVal(),
+    nativeToScVal(BigInt(escrowId), { type: "u64" }),
+    split,
+  ];
+
+  const op = contract.call("offer_settlement", ...args);
+
+  return await submitTx(partyAddress, op);
+}
+
+export async function acceptSettlement(
+  partyAddress: string,
+  escrowId: string
+) {
+  const contract = new Contract(CONTRACT_ID);
+
+  const args = [
+    new Address(partyAddress).toScVal(),
+    nativeToScVal(BigInt(escrowId), { type: "u64" }),
+  ];
+
+  const op = contract.call("accept_settlement", ...args);
+
+  return await submitTx(partyAddress, op);
+}
+
 // Helper to clean up BigInts for JSON/Display
 function sanitizeScVal(val: unknown): unknown {
   if (typeof val === 'bigint') {
@@ -171,11 +403,23 @@ export async function getEscrow(escrowId: string, userAddress?: string) {
     // @ts-expect-error - simulation result structure varies by SDK version
     const retval = simulation.result ? simulation.result.retval : simulation.results[0].retval;
     const raw = scValToNative(retval);
-    return sanitizeScVal(raw);
+    return withCurrentMilestone(sanitizeScVal(raw) as Record<string, unknown>);
   }
   return null;
 }
 
+// Approval flags live on each milestone. Surface the ones of the current
+// (first unreleased) milestone at the top level for the UI.
+function withCurrentMilestone(escrow: Record<string, unknown>) {
+  const milestones = (escrow.milestones as Array<Record<string, unknown>>) ?? [];
+  const current = milestones.find(m => !m.released);
+  return {
+    ...escrow,
+    approved_by_client: Boolean(current?.approved_by_client),
+    approved_by_freelancer: Boolean(current?.approved_by_freelancer),
+  };
+}
+
 export async function getUserEscrows(userAddress: string) {
   const contract = new Contract(CONTRACT_ID);
   const op = contract.call("get_user_escrows", new Address(userAddress).toScVal()); // Updated to correct Address format
@@ -199,6 +443,74 @@ export async function getUserEscrows(userAddress: string) {
   return [];
 }
 
+// Fetches full escrow records for a user, one page at a time, following
+// `next_cursor` until the index is exhausted. `state`/`role` map to the
+// contract's EscrowState / Role enums (null = no filter).
+export async function getUserEscrowsPage(
+  userAddress: string,
+  state: number | null = null,
+  role: number | null = null,
+  pageSize = 50
+) {
+  const contract = new Contract(CONTRACT_ID);
+  const account = await server.getAccount(userAddress).catch(() => new Account(userAddress, "0"));
+  const entries: Array<{ id: string; escrow: Record<string, unknown> }> = [];
+
+  let cursor: number | null = 0;
+  while (cursor !== null) {
+    const op = contract.call(
+      "get_user_escrows_page",
+      new Address(userAddress).toScVal(),
+      state === null ? xdr.ScVal.scvVoid() : nativeToScVal(state, { type: "u32" }),
+      role === null ? xdr.ScVal.scvVoid() : nativeToScVal(role, { type: "u32" }),
+      nativeToScVal(cursor, { type: "u32" }),
+      nativeToScVal(pageSize, { type: "u32" }),
+    );
+    const tx = new TransactionBuilder(account, { fee: "100", networkPassphrase: NETWORK_PASSPHRASE })
+      .addOperation(op)
+      .setTimeout(30)
+      .build();
+
+    const simulation = await server.simulateTransaction(tx);
+    if (!rpc.Api.isSimulationSuccess(simulation)) break;
+
+    // @ts-expect-error - simulation result structure varies by SDK version
+    const retval = simulation.result ? simulation.result.retval : simulation.results[0].retval;
+    const page = sanitizeScVal(scValToNative(retval)) as {
+      escrows: Array<{ id: string; escrow: Record<string, unknown> }>;
+      next_cursor?: string | null;
+    };
+    for (const entry of page.escrows) {
+      entries.push({ id: entry.id, escrow: withCurrentMilestone(entry.escrow) });
+    }
+    cursor = page.next_cursor == null ? null : Number(page.next_cursor);
+  }
+
+  return entries;
+}
+
+// Tokens accepted by `deposit`, with their optional min/max amounts
+export async function getAllowedTokens(userAddress?: string) {
+  const contract = new Contract(CONTRACT_ID);
+  const op = contract.call("get_allowed_tokens");
+
+  const SIM_SOURCE = userAddress || "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF";
+  const source = await server.getAccount(SIM_SOURCE).catch(() => new Account(SIM_SOURCE, "0"));
+
+  const tx = new TransactionBuilder(source, { fee: "100", networkPassphrase: NETWORK_PASSPHRASE })
+    .addOperation(op)
+    .setTimeout(30)
+    .build();
+
+  const simulation = await server.simulateTransaction(tx);
+
+  if (rpc.Api.isSimulationSuccess(simulation)) {
+    // @ts-expect-error - simulation result structure varies by SDK version
+    const retval = simulation.result ? simulation.result.retval : simulation.results[0].retval;
+    return sanitizeScVal(scValToNative(retval)) as Array<{ token: string; limits: { min_amount?: string; max_amount?: string } }>;
+  }
+  return [];
+}
 
 // Helper to sign, submit, and POLL for transaction result
 async function submitTx(sourceAddress: string, operation: xdr.Operation) {
@@ -210,7 +522,15 @@ async function submitTx(sourceAddress: string, operation: xdr.Operation) {
     .build();
 
   // IMPORTANT: Simulate and Prepare the transaction (calculate fees & auth)
-  tx = await server.prepareTransaction(tx);
+  try {
+    tx = await server.prepareTransaction(tx);
+  } catch (err) {
+    const code = getContractErrorCode(err);
+    if (code !== null && CONTRACT_ERRORS[code]) {
+      throw new Error(`${CONTRACT_ERRORS[code]} (code ${code})`);
+    }
+    throw err;
+  }
 
   const signedTx = await signTransaction(tx.toXDR(), { networkPassphrase: NETWORK_PASSPHRASE });
 
@@ -0,0 +1,56 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Sql(rusqlite::Error),
+    Xdr(soroban_sdk::xdr::Error),
+    Http(String),
+    Rpc(i64, String), // JSON-RPC error code and message
+    Config(String),
+    MalformedEvent(String), // Event id and what was wrong with it
+    UnsupportedSchema(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io: {e}"),
+            Error::Json(e) => write!(f, "json: {e}"),
+            Error::Sql(e) => write!(f, "sqlite: {e}"),
+            Error::Xdr(e) => write!(f, "xdr: {e}"),
+            Error::Http(e) => write!(f, "http: {e}"),
+            Error::Rpc(code, message) => write!(f, "rpc error {code}: {message}"),
+            Error::Config(e) => write!(f, "{e}"),
+            Error::MalformedEvent(e) => write!(f, "malformed event {e}"),
+            Error::UnsupportedSchema(v) => write!(f, "unsupported event schema version {v}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Sql(e)
+    }
+}
+
+impl From<soroban_sdk::xdr::Error> for Error {
+    fn from(e: soroban_sdk::xdr::Error) -> Self {
+        Error::Xdr(e)
+    }
+}
@@ -0,0 +1,162 @@
+use safe_hands_contract::{
+    AcceptEvent, AmendEvent, ApproveEvent, BountyEvent, DepositEvent, DisputeEvent, EventHeader, EvidenceEvent,
+    OfferEvent, PayoutEvent, ProposalEvent, ProposalWithdrawnEvent, RefundEvent, ReleaseEvent, StopEvent,
+    TimeoutEvent, VoteEvent, WithdrawEvent, EVENT_VERSION,
+};
+use serde::{Deserialize, Serialize};
+use soroban_sdk::xdr::{Limits, ReadXdr, ScAddress, ScVal};
+use soroban_sdk::{Address, Env, TryFromVal, Val};
+
+use crate::error::Error;
+
+/// A contract event as returned by the RPC `getEvents` method. Offline
+/// dumps use the same shape.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RawEvent {
+    pub id: String, // Zero-padded paging token; sorts in chain order
+    pub ledger: u32,
+    #[serde(default)]
+    pub ledger_closed_at: String,
+    #[serde(default)]
+    pub contract_id: String,
+    #[serde(default)]
+    pub tx_hash: String,
+    pub topic: Vec<String>, // Base64 XDR `ScVal`s
+    pub value: String, // Base64 XDR `ScVal`
+    #[serde(default = "default_successful")]
+    pub in_successful_contract_call: bool,
+}
+
+fn default_successful() -> bool {
+    true
+}
+
+/// The `result` of a `getEvents` call.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventPage {
+    pub events: Vec<RawEvent>,
+    #[serde(default)]
+    pub latest_ledger: u32,
+    #[serde(default)]
+    pub oldest_ledger: Option<u32>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+// A dump is either a saved `getEvents` result or a bare list of events
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Dump {
+    Page(EventPage),
+    Events(Vec<RawEvent>),
+}
+
+pub fn parse_dump(json: &str) -> Result<Vec<RawEvent>, Error> {
+    Ok(match serde_json::from_str(json)? {
+        Dump::Page(page) => page.events,
+        Dump::Events(events) => events,
+    })
+}
+
+/// An escrow event decoded into the contract's own event struct.
+#[derive(Clone, Debug)]
+pub enum Transition {
+    Deposit(DepositEvent),
+    Accept(AcceptEvent),
+    Approve(ApproveEvent),
+    Release(ReleaseEvent),
+    Refund(RefundEvent), // `cancel` or `decline`
+    Withdraw(WithdrawEvent),
+    Stop(StopEvent),
+    Proposal(ProposalEvent),
+    ProposalWithdrawn(ProposalWithdrawnEvent),
+    Amend(AmendEvent),
+    Dispute(DisputeEvent),
+    Evidence(EvidenceEvent),
+    Offer(OfferEvent),
+    Vote(VoteEvent),
+    Payout(PayoutEvent), // `settle`, `resolve` or `fallback`
+    Timeout(TimeoutEvent),
+    Bounty(BountyEvent),
+}
+
+impl Transition {
+    pub fn header(&self) -> &EventHeader {
+        match self {
+            Transition::Deposit(e) => &e.header,
+            Transition::Accept(e) => &e.header,
+            Transition::Approve(e) => &e.header,
+            Transition::Release(e) => &e.header,
+            Transition::Refund(e) => &e.header,
+            Transition::Withdraw(e) => &e.header,
+            Transition::Stop(e) => &e.header,
+            Transition::Proposal(e) => &e.header,
+            Transition::ProposalWithdrawn(e) => &e.header,
+            Transition::Amend(e) => &e.header,
+            Transition::Dispute(e) => &e.header,
+            Transition::Evidence(e) => &e.header,
+            Transition::Offer(e) => &e.header,
+            Transition::Vote(e) => &e.header,
+            Transition::Payout(e) => &e.header,
+            Transition::Timeout(e) => &e.header,
+            Transition::Bounty(e) => &e.header,
+        }
+    }
+}
+
+/// Decodes an escrow event, returning its topic name alongside it.
+/// Admin events (`init`, `pause`, `fee_cfg`, ...) and events from failed
+/// calls give `None`.
+pub fn decode(env: &Env, raw: &RawEvent) -> Result<Option<(String, Transition)>, Error> {
+    if !raw.in_successful_contract_call {
+        return Ok(None);
+    }
+    let malformed = |what: &str| Error::MalformedEvent(format!("{}: {what}", raw.id));
+
+    let first = raw.topic.first().ok_or_else(|| malformed("no topics"))?;
+    let name = match ScVal::from_xdr_base64(first, Limits::none())? {
+        ScVal::Symbol(symbol) => symbol.to_utf8_string().map_err(|_| malformed("topic is not utf-8"))?,
+        _ => return Err(malformed("first topic is not a symbol")),
+    };
+    let value = ScVal::from_xdr_base64(&raw.value, Limits::none())?;
+
+    let value = Val::try_from_val(env, &value).map_err(|_| malformed("value does not convert to a host value"))?;
+
+    let transition = match name.as_str() {
+        "deposit" => Transition::Deposit(parse(env, raw, &value)?),
+        "accept" => Transition::Accept(parse(env, raw, &value)?),
+        "approve" => Transition::Approve(parse(env, raw, &value)?),
+        "release" => Transition::Release(parse(env, raw, &value)?),
+        "cancel" | "decline" => Transition::Refund(parse(env, raw, &value)?),
+        "withdraw" => Transition::Withdraw(parse(env, raw, &value)?),
+        "stop" => Transition::Stop(parse(env, raw, &value)?),
+        "proposal" => Transition::Proposal(parse(env, raw, &value)?),
+        "prop_wd" => Transition::ProposalWithdrawn(parse(env, raw, &value)?),
+        "amend" => Transition::Amend(parse(env, raw, &value)?),
+        "dispute" => Transition::Dispute(parse(env, raw, &value)?),
+        "evidence" => Transition::Evidence(parse(env, raw, &value)?),
+        "offer" => Transition::Offer(parse(env, raw, &value)?),
+        "vote" => Transition::Vote(parse(env, raw, &value)?),
+        "settle" | "resolve" | "fallback" => Transition::Payout(parse(env, raw, &value)?),
+        "timeout" => Transition::Timeout(parse(env, raw, &value)?),
+        "bounty" => Transition::Bounty(parse(env, raw, &value)?),
+        _ => return Ok(None),
+    };
+
+    let schema = transition.header().schema;
+    if schema != EVENT_VERSION {
+        return Err(Error::UnsupportedSchema(schema));
+    }
+    Ok(Some((name, transition)))
+}
+
+fn parse<T: TryFromVal<Env, Val>>(env: &Env, raw: &RawEvent, value: &Val) -> Result<T, Error> {
+    T::try_from_val(env, value).map_err(|_| Error::MalformedEvent(format!("{}: value does not match its topic", raw.id)))
+}
+
+/// The strkey (`G...`/`C...`) form of an address.
+pub fn strkey(address: &Address) -> String {
+    ScAddress::from(address).to_string()
+}
@@ -0,0 +1,17 @@
+//! Rebuilds SafeHands escrow state from contract events into SQLite, so
+//! dashboards can query escrows without calling the contract.
+
+pub mod error;
+pub mod events;
+pub mod rpc;
+pub mod store;
+
+#[cfg(test)]
+mod test;
+
+use soroban_sdk::Env;
+
+/// Host used only to decode XDR into the contract's types.
+pub fn decode_env() -> Env {
+    Env::default()
+}
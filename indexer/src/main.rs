@@ -0,0 +1,111 @@
+use std::{fs, thread, time::Duration};
+
+use clap::{Parser, Subcommand};
+use safe_hands_indexer::error::Error;
+use safe_hands_indexer::events::parse_dump;
+use safe_hands_indexer::rpc::RpcFeed;
+use safe_hands_indexer::store::{Anomaly, AnomalyKind, Report, Store};
+use safe_hands_indexer::decode_env;
+
+// Ledgers re-read on every poll, so events the RPC server later drops or
+// replaces are caught as reorgs
+const REORG_DEPTH: u32 = 16;
+
+#[derive(Parser)]
+#[command(about = "Index SafeHands escrow events into SQLite")]
+struct Args {
+    /// SQLite database to write
+    #[arg(long, default_value = "safehands.sqlite")]
+    db: String,
+
+    #[command(subcommand)]
+    source: Source,
+}
+
+#[derive(Subcommand)]
+enum Source {
+    /// Index a JSON dump: a saved `getEvents` result or a list of its events.
+    /// The dump is taken to cover every ledger from its first event on.
+    Dump { path: String },
+
+    /// Index from a Soroban RPC server
+    Rpc {
+        #[arg(long)]
+        url: String,
+        /// SafeHands contract address
+        #[arg(long)]
+        contract: String,
+        /// First ledger to read when the database is empty
+        #[arg(long)]
+        start_ledger: Option<u32>,
+        /// Keep polling for new events
+        #[arg(long)]
+        follow: bool,
+        #[arg(long, default_value_t = 5)]
+        poll_secs: u64,
+    },
+}
+
+fn main() {
+    if let Err(e) = run(Args::parse()) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    let env = decode_env();
+    let mut store = Store::open(&args.db)?;
+
+    match args.source {
+        Source::Dump { path } => {
+            let events = parse_dump(&fs::read_to_string(path)?)?;
+            let from_ledger = events.iter().map(|e| e.ledger).min().unwrap_or(0);
+            let to_ledger = events.iter().map(|e| e.ledger).max().unwrap_or(0);
+            let report = store.ingest(&env, &events, from_ledger, to_ledger)?;
+            print_report(&report, to_ledger);
+        }
+        Source::Rpc { url, contract, start_ledger, follow, poll_secs } => {
+            let feed = RpcFeed::new(&url, &contract);
+            loop {
+                let last = store.last_ledger()?;
+                let start = match (last, start_ledger) {
+                    (Some(last), _) => last.saturating_sub(REORG_DEPTH).max(1),
+                    (None, Some(start)) => start,
+                    (None, None) => {
+                        return Err(Error::Config("--start-ledger is required for an empty database".into()))
+                    }
+                };
+
+                let fetched = feed.fetch(start)?;
+                if let Some(last) = last.filter(|last| fetched.start_ledger > last + 1) {
+                    print_anomaly(&store.record_ledger_gap(last + 1, fetched.start_ledger - 1)?);
+                }
+                let report = store.ingest(&env, &fetched.events, fetched.start_ledger, fetched.latest_ledger)?;
+                print_report(&report, fetched.latest_ledger);
+
+                if !follow {
+                    break;
+                }
+                thread::sleep(Duration::from_secs(poll_secs));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_report(report: &Report, ledger: u32) {
+    report.anomalies.iter().for_each(print_anomaly);
+    println!(
+        "ledger {ledger}: {} new events, {} already indexed, {} rolled back, {} gaps, {} reorgs",
+        report.applied, report.already_indexed, report.rolled_back, report.gaps, report.reorgs,
+    );
+}
+
+fn print_anomaly(anomaly: &Anomaly) {
+    let kind = match anomaly.kind {
+        AnomalyKind::Gap => "gap",
+        AnomalyKind::Reorg => "reorg",
+    };
+    eprintln!("{kind}: {}", anomaly.detail);
+}
@@ -0,0 +1,86 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::events::{EventPage, RawEvent};
+
+// Events requested per `getEvents` page
+const PAGE_LIMIT: usize = 200;
+
+/// Reads SafeHands events from a Soroban RPC server.
+pub struct RpcFeed {
+    url: String,
+    contract_id: String,
+}
+
+/// Every event from `start_ledger` up to the server's latest ledger.
+pub struct Fetched {
+    pub events: Vec<RawEvent>,
+    pub start_ledger: u32, // Raised to the oldest ledger the server still keeps
+    pub latest_ledger: u32,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcFeed {
+    pub fn new(url: &str, contract_id: &str) -> Self {
+        RpcFeed { url: url.to_string(), contract_id: contract_id.to_string() }
+    }
+
+    pub fn fetch(&self, start_ledger: u32) -> Result<Fetched, Error> {
+        let filters = json!([{ "type": "contract", "contractIds": [self.contract_id] }]);
+        let mut params = json!({
+            "startLedger": start_ledger,
+            "filters": filters,
+            "pagination": { "limit": PAGE_LIMIT },
+        });
+
+        let mut fetched = Fetched { events: Vec::new(), start_ledger, latest_ledger: 0 };
+        loop {
+            let page: EventPage = self.call("getEvents", params)?;
+            let full = page.events.len() >= PAGE_LIMIT;
+            let cursor = page.cursor.or_else(|| page.events.last().map(|e| e.id.clone()));
+
+            fetched.start_ledger = fetched.start_ledger.max(page.oldest_ledger.unwrap_or(0));
+            fetched.latest_ledger = page.latest_ledger;
+            fetched.events.extend(page.events);
+
+            match cursor {
+                Some(cursor) if full => {
+                    params = json!({
+                        "filters": filters,
+                        "pagination": { "cursor": cursor, "limit": PAGE_LIMIT },
+                    });
+                }
+                _ => return Ok(fetched),
+            }
+        }
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let text = ureq::post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .map_err(|e| Error::Http(e.to_string()))?
+            .into_string()?;
+
+        let response: Response<T> = serde_json::from_str(&text)?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(Error::Rpc(error.code, error.message)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(Error::Rpc(0, format!("{method} returned no result"))),
+        }
+    }
+}
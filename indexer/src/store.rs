@@ -0,0 +1,458 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use safe_hands_contract::EscrowState;
+use soroban_sdk::Env;
+
+use crate::error::Error;
+use crate::events::{decode, strkey, RawEvent, Transition};
+
+// Amounts are i128 and stored as decimal text
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS escrows (
+    id INTEGER PRIMARY KEY,
+    client TEXT, -- NULL if the deposit event was never seen
+    freelancer TEXT,
+    token TEXT NOT NULL,
+    amount TEXT,
+    state INTEGER NOT NULL,
+    deadline INTEGER NOT NULL,
+    arbitration_deadline INTEGER NOT NULL DEFAULT 0,
+    locked_amount TEXT NOT NULL,
+    released_amount TEXT NOT NULL,
+    version INTEGER NOT NULL DEFAULT 0,
+    last_event TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY, -- RPC paging token
+    ledger INTEGER NOT NULL,
+    tx_hash TEXT NOT NULL,
+    escrow_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    state INTEGER NOT NULL, -- Escrow state once the event was applied
+    topic TEXT NOT NULL, -- JSON array of base64 XDR topics
+    value TEXT NOT NULL -- Base64 XDR event struct
+);
+CREATE INDEX IF NOT EXISTS events_by_escrow ON events (escrow_id, id);
+CREATE INDEX IF NOT EXISTS events_by_ledger ON events (ledger);
+CREATE TABLE IF NOT EXISTS anomalies (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL, -- 'gap' or 'reorg'
+    escrow_id INTEGER,
+    event_id TEXT,
+    detail TEXT NOT NULL
+);
+";
+
+/// An escrow as rebuilt from its events. Milestones, panel and terms are
+/// not carried by events; read them with `get_escrow` when needed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EscrowRecord {
+    pub id: u64,
+    pub client: Option<String>,
+    pub freelancer: Option<String>,
+    pub token: String,
+    pub amount: Option<i128>,
+    pub state: EscrowState,
+    pub deadline: u64,
+    pub arbitration_deadline: u64,
+    pub locked_amount: i128,
+    pub released_amount: i128,
+    pub version: u32,
+    pub last_event: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub event_id: String,
+    pub ledger: u32,
+    pub name: String,
+    pub state: EscrowState,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnomalyKind {
+    Gap, // Events are missing from the sequence
+    Reorg, // Indexed events were replaced or dropped by the feed and rolled back
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub escrow_id: Option<u64>,
+    pub event_id: Option<String>,
+    pub detail: String,
+}
+
+/// What one `ingest` call did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub applied: u32,
+    pub already_indexed: u32,
+    pub rolled_back: u32,
+    pub gaps: u32,
+    pub reorgs: u32,
+    pub anomalies: Vec<Anomaly>, // Recorded by this call, in order
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &str) -> Result<Self, Error> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store { conn })
+    }
+
+    /// Highest ledger with an indexed event.
+    pub fn last_ledger(&self) -> Result<Option<u32>, Error> {
+        Ok(self.conn.query_row("SELECT MAX(ledger) FROM events", [], |row| row.get(0))?)
+    }
+
+    /// Index a run of events covering every ledger from `from_ledger` to
+    /// `to_ledger`. Events already indexed in that range are compared with
+    /// the feed: the first one that differs or is missing is a reorg, and
+    /// everything from there on is rolled back before the feed is applied.
+    /// Indexed events past `to_ledger` are left alone.
+    pub fn ingest(&mut self, env: &Env, events: &[RawEvent], from_ledger: u32, to_ledger: u32) -> Result<Report, Error> {
+        let mut decoded = Vec::new();
+        for raw in events.iter().filter(|e| (from_ledger..=to_ledger).contains(&e.ledger)) {
+            if let Some((name, transition)) = decode(env, raw)? {
+                decoded.push((raw, name, transition));
+            }
+        }
+        decoded.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+
+        let mut report = Report::default();
+        let tx = self.conn.transaction()?;
+
+        let stored = stored_between(&tx, from_ledger, to_ledger)?;
+        let mut next = 0;
+        for (id, tx_hash, value) in stored {
+            match decoded.get(next) {
+                Some((raw, _, _)) if raw.id == id && raw.tx_hash == tx_hash && raw.value == value => next += 1,
+                _ => {
+                    report.rolled_back = rollback(&tx, env, &id)?;
+                    report.reorgs += 1;
+                    report.anomalies.push(record(&tx, AnomalyKind::Reorg, None, Some(&id), &format!(
+                        "feed no longer matches indexed event {id}; rolled back {} events",
+                        report.rolled_back,
+                    ))?);
+                    break;
+                }
+            }
+        }
+        report.already_indexed = next as u32;
+
+        for (raw, name, transition) in &decoded[next..] {
+            let gaps = apply(&tx, raw, name, transition, true)?;
+            report.gaps += gaps.len() as u32;
+            report.anomalies.extend(gaps);
+            report.applied += 1;
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Record ledgers the feed can no longer serve, e.g. past RPC retention.
+    pub fn record_ledger_gap(&self, from_ledger: u32, to_ledger: u32) -> Result<Anomaly, Error> {
+        let detail = format!("ledgers {from_ledger}..={to_ledger} were never indexed");
+        record(&self.conn, AnomalyKind::Gap, None, None, &detail)
+    }
+
+    pub fn escrow(&self, id: u64) -> Result<Option<EscrowRecord>, Error> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id, client, freelancer, token, amount, state, deadline, arbitration_deadline,
+                        locked_amount, released_amount, version, last_event
+                 FROM escrows WHERE id = ?1",
+                [id as i64],
+                |row| {
+                    Ok(EscrowRecord {
+                        id: row.get::<_, i64>(0)? as u64,
+                        client: row.get(1)?,
+                        freelancer: row.get(2)?,
+                        token: row.get(3)?,
+                        amount: row.get::<_, Option<Amount>>(4)?.map(|a| a.0),
+                        state: row.get::<_, State>(5)?.0,
+                        deadline: row.get::<_, i64>(6)? as u64,
+                        arbitration_deadline: row.get::<_, i64>(7)? as u64,
+                        locked_amount: row.get::<_, Amount>(8)?.0,
+                        released_amount: row.get::<_, Amount>(9)?.0,
+                        version: row.get(10)?,
+                        last_event: row.get(11)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    /// An escrow's indexed events, oldest first.
+    pub fn history(&self, escrow_id: u64) -> Result<Vec<HistoryEntry>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, ledger, name, state FROM events WHERE escrow_id = ?1 ORDER BY id")?;
+        let rows = stmt.query_map([escrow_id as i64], |row| {
+            Ok(HistoryEntry {
+                event_id: row.get(0)?,
+                ledger: row.get(1)?,
+                name: row.get(2)?,
+                state: row.get::<_, State>(3)?.0,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn anomalies(&self) -> Result<Vec<Anomaly>, Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT kind, escrow_id, event_id, detail FROM anomalies ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Anomaly {
+                kind: if row.get::<_, String>(0)? == "reorg" { AnomalyKind::Reorg } else { AnomalyKind::Gap },
+                escrow_id: row.get::<_, Option<i64>>(1)?.map(|id| id as u64),
+                event_id: row.get(2)?,
+                detail: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+fn stored_between(tx: &Transaction, from_ledger: u32, to_ledger: u32) -> Result<Vec<(String, String, String)>, Error> {
+    let mut stmt = tx.prepare("SELECT id, tx_hash, value FROM events WHERE ledger BETWEEN ?1 AND ?2 ORDER BY id")?;
+    let rows = stmt.query_map([from_ledger, to_ledger], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+// Drops every event from `from_id` on and rebuilds the escrows they touched
+// from the events that remain. Returns the number of events dropped.
+fn rollback(tx: &Transaction, env: &Env, from_id: &str) -> Result<u32, Error> {
+    let affected: Vec<i64> = {
+        let mut stmt = tx.prepare("SELECT DISTINCT escrow_id FROM events WHERE id >= ?1")?;
+        let rows = stmt.query_map([from_id], |row| row.get(0))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let dropped = tx.execute("DELETE FROM events WHERE id >= ?1", [from_id])? as u32;
+
+    for escrow_id in affected {
+        tx.execute("DELETE FROM escrows WHERE id = ?1", [escrow_id])?;
+        let remaining: Vec<RawEvent> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, ledger, tx_hash, topic, value FROM events WHERE escrow_id = ?1 ORDER BY id",
+            )?;
+            let rows = stmt.query_map([escrow_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?, row.get::<_, String>(3)?, row.get(4)?))
+            })?;
+            let mut remaining = Vec::new();
+            for row in rows {
+                let (id, ledger, tx_hash, topic, value) = row?;
+                remaining.push(RawEvent {
+                    id,
+                    ledger,
+                    ledger_closed_at: String::new(),
+                    contract_id: String::new(),
+                    tx_hash,
+                    topic: serde_json::from_str(&topic)?,
+                    value,
+                    in_successful_contract_call: true,
+                });
+            }
+            remaining
+        };
+        tx.execute("DELETE FROM events WHERE escrow_id = ?1", [escrow_id])?;
+
+        // Already checked when first indexed
+        for raw in &remaining {
+            if let Some((name, transition)) = decode(env, raw)? {
+                apply(tx, raw, &name, &transition, false)?;
+            }
+        }
+    }
+    Ok(dropped)
+}
+
+// Applies one event to its escrow and the history. With `check`, events
+// that don't follow from what is indexed are recorded as gaps and
+// returned.
+fn apply(
+    tx: &Transaction,
+    raw: &RawEvent,
+    name: &str,
+    transition: &Transition,
+    check: bool,
+) -> Result<Vec<Anomaly>, Error> {
+    let header = transition.header();
+    let escrow_id = header.escrow_id as i64;
+    let previous: Option<(State, Amount)> = tx
+        .query_row(
+            "SELECT state, released_amount FROM escrows WHERE id = ?1",
+            [escrow_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let mut gaps = Vec::new();
+    if check {
+        let mut gap = |detail: String| -> Result<(), Error> {
+            gaps.push(record(tx, AnomalyKind::Gap, Some(header.escrow_id), Some(&raw.id), &detail)?);
+            Ok(())
+        };
+        match (&previous, transition) {
+            (None, Transition::Deposit(_)) => {
+                let last: Option<i64> = tx.query_row("SELECT MAX(id) FROM escrows", [], |row| row.get(0))?;
+                if let Some(last) = last.filter(|last| escrow_id > last + 1) {
+                    gap(format!("deposits for escrows {}..{} were never seen", last + 1, escrow_id))?;
+                }
+            }
+            (None, _) => gap(format!("{name} for escrow {escrow_id}, whose deposit was never seen"))?,
+            (Some(_), Transition::Deposit(_)) => gap(format!("second deposit event for escrow {escrow_id}"))?,
+            (Some((State(state), Amount(released))), _) => {
+                if is_final(*state) && !after_close(transition) {
+                    gap(format!("{name} for escrow {escrow_id} after it was closed"))?;
+                } else if header.released_amount < *released {
+                    gap(format!("{name} for escrow {escrow_id} reports less released than already indexed"))?;
+                }
+            }
+        }
+    }
+
+    let state = header.state as u32;
+    if previous.is_none() {
+        tx.execute(
+            "INSERT INTO escrows (id, token, state, deadline, locked_amount, released_amount, last_event)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                escrow_id,
+                strkey(&header.token),
+                state,
+                header.deadline as i64,
+                header.locked_amount.to_string(),
+                header.released_amount.to_string(),
+                raw.id,
+            ],
+        )?;
+    } else {
+        tx.execute(
+            "UPDATE escrows SET state = ?2, deadline = ?3, locked_amount = ?4, released_amount = ?5, last_event = ?6
+             WHERE id = ?1",
+            params![
+                escrow_id,
+                state,
+                header.deadline as i64,
+                header.locked_amount.to_string(),
+                header.released_amount.to_string(),
+                raw.id,
+            ],
+        )?;
+    }
+
+    match transition {
+        Transition::Deposit(e) => {
+            tx.execute(
+                "UPDATE escrows SET client = ?2, freelancer = ?3, amount = ?4 WHERE id = ?1",
+                params![escrow_id, strkey(&e.client), strkey(&e.freelancer), e.amount.to_string()],
+            )?;
+        }
+        Transition::Dispute(e) => {
+            tx.execute(
+                "UPDATE escrows SET arbitration_deadline = ?2 WHERE id = ?1",
+                params![escrow_id, e.arbitration_deadline as i64],
+            )?;
+        }
+        Transition::Amend(e) => {
+            tx.execute(
+                "UPDATE escrows SET version = ?2, amount = ?3 WHERE id = ?1",
+                params![escrow_id, e.version, e.amount.to_string()],
+            )?;
+        }
+        _ => {}
+    }
+
+    tx.execute(
+        "INSERT INTO events (id, ledger, tx_hash, escrow_id, name, state, topic, value)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            raw.id,
+            raw.ledger,
+            raw.tx_hash,
+            escrow_id,
+            name,
+            state,
+            serde_json::to_string(&raw.topic)?,
+            raw.value,
+        ],
+    )?;
+    Ok(gaps)
+}
+
+fn record(
+    conn: &Connection,
+    kind: AnomalyKind,
+    escrow_id: Option<u64>,
+    event_id: Option<&str>,
+    detail: &str,
+) -> Result<Anomaly, Error> {
+    let code = match kind {
+        AnomalyKind::Gap => "gap",
+        AnomalyKind::Reorg => "reorg",
+    };
+    conn.execute(
+        "INSERT INTO anomalies (kind, escrow_id, event_id, detail) VALUES (?1, ?2, ?3, ?4)",
+        params![code, escrow_id.map(|id| id as i64), event_id, detail],
+    )?;
+    Ok(Anomaly { kind, escrow_id, event_id: event_id.map(String::from), detail: detail.into() })
+}
+
+fn is_final(state: EscrowState) -> bool {
+    matches!(
+        state,
+        EscrowState::Released | EscrowState::Cancelled | EscrowState::Resolved | EscrowState::Settled
+    )
+}
+
+// Events the contract still emits once an escrow is closed: the keeper
+// bounty right after a timeout or fallback, and a pending amendment being
+// withdrawn
+fn after_close(transition: &Transition) -> bool {
+    matches!(transition, Transition::Bounty(_) | Transition::ProposalWithdrawn(_))
+}
+
+// An `EscrowState` stored as its contract code
+struct State(EscrowState);
+
+impl FromSql for State {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let state = match i64::column_result(value)? {
+            0 => EscrowState::Funded,
+            1 => EscrowState::Released,
+            2 => EscrowState::Cancelled,
+            3 => EscrowState::Disputed,
+            4 => EscrowState::Resolved,
+            5 => EscrowState::Accepted,
+            6 => EscrowState::Settled,
+            code => return Err(FromSqlError::OutOfRange(code)),
+        };
+        Ok(State(state))
+    }
+}
+
+// An i128 amount stored as decimal text
+struct Amount(i128);
+
+impl FromSql for Amount {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let text = value.as_str()?;
+        text.parse().map(Amount).map_err(|e| FromSqlError::Other(Box::new(e)))
+    }
+}
@@ -0,0 +1,280 @@
+extern crate std;
+
+use safe_hands_contract::{
+    EscrowKind, EscrowOptions, EscrowState, Fallback, SafeHandsContract, SafeHandsContractClient,
+};
+use soroban_sdk::testutils::{Address as _, EnvTestConfig, Events, Ledger};
+use soroban_sdk::xdr::{Limits, ScVal, WriteXdr};
+use soroban_sdk::{token, vec, Address, BytesN, Env, TryFromVal, Val};
+
+use crate::events::{parse_dump, strkey, RawEvent};
+use crate::store::{AnomalyKind, Store};
+
+// The crate's `decode_env` writes a test snapshot when dropped under
+// testutils, so tests decode with one that doesn't
+fn test_env() -> Env {
+    Env::new_with_config(EnvTestConfig { capture_snapshot_at_drop: false })
+}
+
+// Appends the contract events not yet in `feed`, as the RPC server would
+// return them from `ledger`
+fn capture(env: &Env, contract_id: &Address, ledger: u32, feed: &mut Vec<RawEvent>) {
+    let base64 = |val: &Val| ScVal::try_from_val(env, val).unwrap().to_xdr_base64(Limits::none()).unwrap();
+    let events = env.events().all();
+    let seen = feed.len();
+    let new = events.iter().filter(|(id, _, _)| id == contract_id).skip(seen);
+    for (index, (id, topics, data)) in new.enumerate() {
+        feed.push(RawEvent {
+            id: format!("{:019}-{:010}", (ledger as u64) << 32, index),
+            ledger,
+            ledger_closed_at: String::new(),
+            contract_id: strkey(&id),
+            tx_hash: format!("{ledger:064x}"),
+            topic: topics.iter().map(|t| base64(&t)).collect(),
+            value: base64(&data),
+            in_successful_contract_call: true,
+        });
+    }
+}
+
+// Two escrows: #0 is released, #1 disputed and resolved for the client.
+// Returns the event feed, the client and the freelancer.
+fn escrow_feed() -> (Vec<RawEvent>, Address, Address) {
+    let env = test_env();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let arbiter = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let mut feed = Vec::new();
+    let mut ledger = 100;
+    let mut step = |feed: &mut Vec<RawEvent>, call: &dyn Fn()| {
+        call();
+        capture(&env, &contract_id, ledger, feed);
+        ledger += 1;
+    };
+
+    step(&mut feed, &|| {
+        client.deposit(&user_a, &user_b, &arbiter, &token_contract, &100_i128, &30);
+    });
+    step(&mut feed, &|| {
+        client.deposit(&user_a, &user_b, &arbiter, &token_contract, &200_i128, &30);
+    });
+    step(&mut feed, &|| client.accept(&user_b, &0, &client.get_escrow(&0).terms_hash));
+    step(&mut feed, &|| client.approve(&user_a, &0));
+    step(&mut feed, &|| client.approve(&user_b, &0));
+    step(&mut feed, &|| client.dispute(&user_a, &1));
+    step(&mut feed, &|| client.resolve(&arbiter, &1, &user_a));
+
+    (feed, user_a, user_b)
+}
+
+#[test]
+fn test_rebuild_from_dump() {
+    let env = test_env();
+    let (feed, user_a, user_b) = escrow_feed();
+    let dump = serde_json::to_string(&feed).unwrap();
+    let events = parse_dump(&dump).unwrap();
+
+    let mut store = Store::open_in_memory().unwrap();
+    let report = store.ingest(&env, &events, 0, 106).unwrap();
+    assert_eq!(report.applied, 8);
+    assert_eq!(report.gaps, 0);
+    assert_eq!(store.last_ledger().unwrap(), Some(106));
+
+    let released = store.escrow(0).unwrap().unwrap();
+    assert_eq!(released.state, EscrowState::Released);
+    assert_eq!(released.client, Some(strkey(&user_a)));
+    assert_eq!(released.freelancer, Some(strkey(&user_b)));
+    assert_eq!(released.amount, Some(100));
+    assert_eq!(released.locked_amount, 0);
+    assert_eq!(released.released_amount, 100);
+
+    let names: Vec<String> = store.history(0).unwrap().into_iter().map(|h| h.name).collect();
+    assert_eq!(names, ["deposit", "accept", "approve", "approve", "release"]);
+
+    let resolved = store.escrow(1).unwrap().unwrap();
+    assert_eq!(resolved.state, EscrowState::Resolved);
+    assert!(resolved.arbitration_deadline > 0);
+    let states: Vec<EscrowState> = store.history(1).unwrap().into_iter().map(|h| h.state).collect();
+    assert_eq!(states, [EscrowState::Funded, EscrowState::Disputed, EscrowState::Resolved]);
+
+    // Feeding the same events again changes nothing
+    let report = store.ingest(&env, &events, 0, 106).unwrap();
+    assert_eq!(report.applied, 0);
+    assert_eq!(report.already_indexed, 8);
+    assert!(store.anomalies().unwrap().is_empty());
+
+    // A saved `getEvents` result works as a dump too
+    let page = format!(r#"{{"events": {dump}, "latestLedger": 106}}"#);
+    assert_eq!(parse_dump(&page).unwrap(), events);
+}
+
+#[test]
+fn test_detect_gaps() {
+    let env = test_env();
+    let (feed, _, _) = escrow_feed();
+
+    // Lose the deposit of escrow #1
+    let events: Vec<RawEvent> = feed.iter().filter(|e| e.ledger != 101).cloned().collect();
+
+    let mut store = Store::open_in_memory().unwrap();
+    let report = store.ingest(&env, &events, 0, 106).unwrap();
+    assert_eq!(report.gaps, 1);
+
+    let anomalies = store.anomalies().unwrap();
+    assert_eq!(report.anomalies, anomalies);
+    assert_eq!(anomalies.len(), 1);
+    assert_eq!(anomalies[0].kind, AnomalyKind::Gap);
+    assert_eq!(anomalies[0].escrow_id, Some(1));
+
+    // Escrow #1 is still tracked from its later events
+    let escrow = store.escrow(1).unwrap().unwrap();
+    assert_eq!(escrow.client, None);
+    assert_eq!(escrow.state, EscrowState::Resolved);
+
+    // An approval showing up after escrow #0 was released
+    let mut stray = feed.iter().find(|e| e.ledger == 103).unwrap().clone();
+    stray.id = format!("{:019}-{:010}", 107_u64 << 32, 0);
+    stray.ledger = 107;
+    let report = store.ingest(&env, &[stray], 107, 107).unwrap();
+    assert_eq!(report.gaps, 1);
+    assert_eq!(store.anomalies().unwrap()[1].escrow_id, Some(0));
+}
+
+#[test]
+fn test_replay_bounty_claim() {
+    let env = test_env();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, SafeHandsContract);
+    let client = SafeHandsContractClient::new(&env, &contract_id);
+
+    let token_admin = Address::generate(&env);
+    let token_contract = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_contract);
+
+    let user_a = Address::generate(&env);
+    let user_b = Address::generate(&env);
+    let keeper = Address::generate(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.allow_token(&token_contract, &None, &None);
+    token_admin_client.mint(&user_a, &1000_i128);
+
+    let options = EscrowOptions {
+        terms_hash: BytesN::from_array(&env, &[0; 32]),
+        terms_uri: None,
+        panel: vec![&env, Address::generate(&env)],
+        threshold: 1,
+        fallback: Fallback::Refund,
+        kind: EscrowKind::LumpSum,
+        keeper_bounty: 10,
+    };
+    let mut feed = Vec::new();
+    client.deposit_with_options(&user_a, &user_b, &token_contract, &vec![&env, 100_i128], &1, &options);
+    capture(&env, &contract_id, 100, &mut feed);
+
+    // The bounty event follows the timeout that closed the escrow
+    env.ledger().with_mut(|li| li.timestamp += 2 * 86400);
+    client.claim_timeout(&keeper, &0);
+    capture(&env, &contract_id, 101, &mut feed);
+
+    let env = test_env();
+    let mut store = Store::open_in_memory().unwrap();
+    let report = store.ingest(&env, &feed, 0, 101).unwrap();
+    assert_eq!(report.applied, 3);
+    assert_eq!(report.gaps, 0);
+    assert!(report.anomalies.is_empty());
+    assert!(store.anomalies().unwrap().is_empty());
+
+    let history = store.history(0).unwrap();
+    let names: Vec<&str> = history.iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(names, ["deposit", "timeout", "bounty"]);
+    assert_eq!(store.escrow(0).unwrap().unwrap().state, EscrowState::Cancelled);
+}
+
+#[test]
+fn test_reorg_rollback() {
+    let env = test_env();
+    let (feed, _, _) = escrow_feed();
+
+    let mut store = Store::open_in_memory().unwrap();
+    store.ingest(&env, &feed, 0, 106).unwrap();
+
+    // The resolution is gone from the chain and the dispute landed in a
+    // different transaction
+    let mut replaced: Vec<RawEvent> = feed.iter().filter(|e| e.ledger == 104 || e.ledger == 105).cloned().collect();
+    replaced.last_mut().unwrap().tx_hash = "ff".repeat(32);
+
+    let report = store.ingest(&env, &replaced, 104, 106).unwrap();
+    assert_eq!(report.reorgs, 1);
+    assert_eq!(report.already_indexed, 2);
+    assert_eq!(report.rolled_back, 2);
+    assert_eq!(report.applied, 1);
+    assert_eq!(report.gaps, 0);
+
+    let escrow = store.escrow(1).unwrap().unwrap();
+    assert_eq!(escrow.state, EscrowState::Disputed);
+    assert_eq!(store.history(1).unwrap().len(), 2);
+    assert_eq!(store.escrow(0).unwrap().unwrap().state, EscrowState::Released);
+
+    let anomalies = store.anomalies().unwrap();
+    assert_eq!(anomalies.len(), 1);
+    assert_eq!(anomalies[0].kind, AnomalyKind::Reorg);
+    assert_eq!(store.last_ledger().unwrap(), Some(105));
+}
+
+#[test]
+fn test_truncated_feed_is_not_reorg() {
+    let env = test_env();
+    let (feed, _, _) = escrow_feed();
+
+    let mut store = Store::open_in_memory().unwrap();
+    store.ingest(&env, &feed, 0, 106).unwrap();
+
+    // A feed that stops at ledger 105 says nothing about the resolution
+    let truncated: Vec<RawEvent> = feed.iter().filter(|e| e.ledger == 104 || e.ledger == 105).cloned().collect();
+    let report = store.ingest(&env, &truncated, 104, 105).unwrap();
+    assert_eq!(report.reorgs, 0);
+    assert_eq!(report.rolled_back, 0);
+    assert_eq!(report.already_indexed, 3);
+    assert_eq!(report.applied, 0);
+
+    assert_eq!(store.escrow(1).unwrap().unwrap().state, EscrowState::Resolved);
+    assert!(store.anomalies().unwrap().is_empty());
+    assert_eq!(store.last_ledger().unwrap(), Some(106));
+}
+
+#[test]
+fn test_unknown_state_code() {
+    let env = test_env();
+    let (feed, _, _) = escrow_feed();
+
+    let path = std::env::temp_dir().join(format!("safe_hands_indexer_{}.db", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut store = Store::open(path).unwrap();
+    store.ingest(&env, &feed, 0, 106).unwrap();
+
+    let conn = rusqlite::Connection::open(path).unwrap();
+    conn.execute("UPDATE escrows SET state = 9 WHERE id = 0", []).unwrap();
+    assert!(store.escrow(0).is_err());
+    assert!(store.escrow(1).is_ok());
+
+    drop(store);
+    std::fs::remove_file(path).unwrap();
+}